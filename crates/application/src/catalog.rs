@@ -0,0 +1,370 @@
+use crate::AppError;
+use crate::kalshi::KalshiClient;
+use crate::market::{Asset, Market, MarketStatus, Venue};
+use crate::polymarket::PolymarketClient;
+use crate::terms::{Direction, MarketTerms, Settlement};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use utoipa::ToSchema;
+
+const GAMMA_PAGE_SIZE: usize = 100;
+const GAMMA_MAX_PAGES: usize = 20;
+const GAMMA_CRYPTO_TAG: &str = "crypto";
+const KALSHI_MAX_PAGES: usize = 20;
+const KALSHI_CRYPTO_CATEGORY: &str = "Crypto";
+
+/// Market together with the contract it describes on the underlying asset.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CatalogEntry {
+    pub market: Market,
+    pub terms: MarketTerms,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CatalogQuery {
+    pub asset: Option<Asset>,
+    pub direction: Option<Direction>,
+    pub settlement: Option<Settlement>,
+    pub min_strike: Option<f64>,
+    pub max_strike: Option<f64>,
+    pub expires_after: Option<DateTime<Utc>>,
+    pub expires_before: Option<DateTime<Utc>>,
+}
+
+/// Active crypto price markets across venues, indexed by underlying asset.
+///
+/// Entries of every asset are sorted by expiry, then by strike.
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketCatalog {
+    pub last_updated: std::time::SystemTime,
+    by_asset: HashMap<Asset, Vec<CatalogEntry>>,
+    /// Active crypto markets whose question names no asset and strike.
+    unmatched: Vec<Market>,
+}
+
+impl Default for MarketCatalog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarketCatalog {
+    pub fn new() -> Self {
+        Self {
+            last_updated: std::time::SystemTime::UNIX_EPOCH,
+            by_asset: HashMap::new(),
+            unmatched: Vec::new(),
+        }
+    }
+
+    /// Builds a catalog from already fetched markets, skipping inactive ones,
+    /// duplicates and those whose question is not a crypto price contract.
+    pub fn from_markets(markets: impl IntoIterator<Item = Market>) -> Self {
+        let mut seen = HashSet::new();
+        let mut by_asset: HashMap<Asset, Vec<CatalogEntry>> = HashMap::new();
+
+        for market in markets {
+            if market.status != MarketStatus::Active
+                || !seen.insert((market.venue, market.id.clone()))
+            {
+                continue;
+            }
+            let Ok(terms) = MarketTerms::try_from(&market) else {
+                continue;
+            };
+            by_asset
+                .entry(terms.asset)
+                .or_default()
+                .push(CatalogEntry { market, terms });
+        }
+
+        for entries in by_asset.values_mut() {
+            entries.sort_by(|a, b| {
+                a.terms
+                    .expiry
+                    .cmp(&b.terms.expiry)
+                    .then(a.terms.strike.total_cmp(&b.terms.strike))
+            });
+        }
+
+        Self {
+            last_updated: std::time::SystemTime::now(),
+            by_asset,
+            unmatched: Vec::new(),
+        }
+    }
+
+    /// Pages through the Gamma `/events` and `/markets` listings and the Kalshi
+    /// crypto events. A venue that fails is skipped, unless every venue fails.
+    pub async fn discover(
+        polymarket: &PolymarketClient,
+        kalshi: &KalshiClient,
+    ) -> Result<Self, AppError> {
+        let (polymarket_markets, kalshi_markets) = tokio::join!(
+            Self::discover_polymarket(polymarket),
+            Self::discover_kalshi(kalshi)
+        );
+
+        let ((tagged, listed), kalshi_markets) = match (polymarket_markets, kalshi_markets) {
+            (Err(e), Err(_)) => return Err(e),
+            (polymarket_markets, kalshi_markets) => (
+                polymarket_markets.unwrap_or_default(),
+                kalshi_markets.unwrap_or_default(),
+            ),
+        };
+
+        // Only the tagged and Kalshi crypto markets are known to be crypto,
+        // the flat Gamma listing has everything.
+        let crypto: Vec<Market> = tagged.iter().chain(&kalshi_markets).cloned().collect();
+        let mut catalog =
+            Self::from_markets(tagged.into_iter().chain(listed).chain(kalshi_markets));
+        let mut seen = HashSet::new();
+        catalog.unmatched = crypto
+            .into_iter()
+            .filter(|market| market.status == MarketStatus::Active)
+            .filter(|market| catalog.get(market.venue, &market.id).is_none())
+            .filter(|market| seen.insert((market.venue, market.id.clone())))
+            .collect();
+        Ok(catalog)
+    }
+
+    /// Markets from the crypto tagged events, then the flat listing. A page
+    /// that fails ends its listing but keeps the pages before it, the venue
+    /// only fails when nothing was fetched at all.
+    async fn discover_polymarket(
+        client: &PolymarketClient,
+    ) -> Result<(Vec<Market>, Vec<Market>), AppError> {
+        let mut tagged = Vec::new();
+        let mut listed = Vec::new();
+        let mut failure = None;
+
+        for page in 0..GAMMA_MAX_PAGES {
+            let events = match client
+                .list_events(
+                    Some(GAMMA_CRYPTO_TAG),
+                    page * GAMMA_PAGE_SIZE,
+                    GAMMA_PAGE_SIZE,
+                )
+                .await
+            {
+                Ok(events) => events,
+                Err(err) => {
+                    eprintln!("Gamma crypto events stopped at page {page}: {err}");
+                    failure = Some(err);
+                    break;
+                }
+            };
+            tagged.extend(
+                events
+                    .items
+                    .into_iter()
+                    .flat_map(|event| event.markets)
+                    .map(Market::from),
+            );
+            if events.returned < GAMMA_PAGE_SIZE {
+                break;
+            }
+        }
+
+        // Not every crypto market is tagged, so scan the flat listing as well.
+        for page in 0..GAMMA_MAX_PAGES {
+            let page_markets = match client
+                .list_markets(page * GAMMA_PAGE_SIZE, GAMMA_PAGE_SIZE)
+                .await
+            {
+                Ok(page_markets) => page_markets,
+                Err(err) => {
+                    eprintln!("Gamma markets stopped at page {page}: {err}");
+                    failure = Some(err);
+                    break;
+                }
+            };
+            listed.extend(page_markets.items.into_iter().map(Market::from));
+            if page_markets.returned < GAMMA_PAGE_SIZE {
+                break;
+            }
+        }
+
+        match failure {
+            Some(err) if tagged.is_empty() && listed.is_empty() => Err(err),
+            _ => Ok((tagged, listed)),
+        }
+    }
+
+    async fn discover_kalshi(client: &KalshiClient) -> Result<Vec<Market>, AppError> {
+        let mut markets = Vec::new();
+        let mut cursor = None;
+
+        for page in 0..KALSHI_MAX_PAGES {
+            let events = match client.list_events(Some("open"), cursor.as_deref()).await {
+                Ok(events) => events,
+                Err(err) if page == 0 => return Err(err),
+                Err(err) => {
+                    eprintln!("Kalshi events stopped at page {page}: {err}");
+                    break;
+                }
+            };
+            markets.extend(
+                events
+                    .items
+                    .into_iter()
+                    .filter(|event| event.category == KALSHI_CRYPTO_CATEGORY)
+                    .flat_map(|event| event.markets)
+                    .map(Market::from),
+            );
+            match events.cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        Ok(markets)
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_asset.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_asset.values().all(Vec::is_empty)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &CatalogEntry> {
+        Asset::ALL
+            .iter()
+            .filter_map(|asset| self.by_asset.get(asset))
+            .flatten()
+    }
+
+    pub fn unmatched(&self) -> &[Market] {
+        &self.unmatched
+    }
+
+    pub fn for_asset(&self, asset: Asset) -> &[CatalogEntry] {
+        self.by_asset.get(&asset).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn get(&self, venue: Venue, id: &str) -> Option<&CatalogEntry> {
+        self.entries()
+            .find(|entry| entry.market.venue == venue && entry.market.id == id)
+    }
+
+    pub fn query<'a>(&'a self, query: &'a CatalogQuery) -> impl Iterator<Item = &'a CatalogEntry> {
+        let entries: Box<dyn Iterator<Item = &CatalogEntry>> = match query.asset {
+            Some(asset) => Box::new(self.for_asset(asset).iter()),
+            None => Box::new(self.entries()),
+        };
+        entries.filter(move |entry| {
+            let terms = &entry.terms;
+            query.direction.is_none_or(|d| terms.direction == d)
+                && query.settlement.is_none_or(|s| terms.settlement == s)
+                && query.min_strike.is_none_or(|s| terms.strike >= s)
+                && query.max_strike.is_none_or(|s| terms.strike <= s)
+                && query
+                    .expires_after
+                    .is_none_or(|t| terms.expiry.is_some_and(|e| e >= t))
+                && query
+                    .expires_before
+                    .is_none_or(|t| terms.expiry.is_some_and(|e| e <= t))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stand_in::{self, StandIn};
+    use serde_json::json;
+
+    fn gamma_market(slug: &str, question: &str, active: bool) -> serde_json::Value {
+        json!({
+            "id": slug,
+            "slug": slug,
+            "question": question,
+            "outcomes": "[\"Yes\", \"No\"]",
+            "outcomePrices": "[\"0.3\", \"0.7\"]",
+            "clobTokenIds": "[\"1\", \"2\"]",
+            "active": active,
+            "closed": !active
+        })
+    }
+
+    #[tokio::test]
+    async fn pages_past_markets_that_do_not_parse() {
+        let mut full_page: Vec<serde_json::Value> = (0..GAMMA_PAGE_SIZE)
+            .map(|i| gamma_market(&format!("m{i}"), "Will it rain?", true))
+            .collect();
+        full_page[7]["outcomePrices"] = json!("[\"n/a\", \"0.7\"]");
+        let stand_in = StandIn::bind().await;
+        let client = PolymarketClient::with_base_url(&stand_in.url);
+        let server = stand_in.serve(vec![
+            (200, "[]".to_string()),
+            (200, json!(full_page).to_string()),
+            (500, String::new()),
+        ]);
+
+        let (tagged, listed) = MarketCatalog::discover_polymarket(&client).await.unwrap();
+        assert!(tagged.is_empty());
+        // The bad market is dropped, the failed second page keeps the first.
+        assert_eq!(listed.len(), GAMMA_PAGE_SIZE - 1);
+
+        let requests = server.await.unwrap();
+        assert!(requests[1].contains("offset=0"));
+        assert!(requests[2].contains(&format!("offset={GAMMA_PAGE_SIZE}")));
+    }
+
+    #[tokio::test]
+    async fn a_venue_fails_when_nothing_was_fetched() {
+        let stand_in = StandIn::bind().await;
+        let client = PolymarketClient::with_base_url(&stand_in.url);
+        let _server = stand_in.serve(vec![(500, String::new()), (503, String::new())]);
+
+        assert!(MarketCatalog::discover_polymarket(&client).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn merges_venues_without_duplicates() {
+        let sol = gamma_market("sol-reach-250", "Will SOL reach $250 by December 31?", true);
+        let dominance = gamma_market("btc-dominance", "Will BTC dominance rise?", true);
+        let (gamma_url, _gamma) =
+            stand_in::serve_json(vec![
+            json!([{"id": "e1", "slug": "solana", "title": "Solana", "markets": [sol, dominance]}])
+                .to_string(),
+            json!([
+                sol,
+                gamma_market("lakers", "Will the Lakers win?", true),
+                gamma_market("eth-above-5000", "Will ETH be above $5,000 on June 30?", false)
+            ])
+            .to_string(),
+        ])
+            .await;
+        let (kalshi_url, _kalshi) = stand_in::serve_json(vec![
+            json!({"events": [
+                {"event_ticker": "KXSOL", "category": "Crypto", "markets": [
+                    {"ticker": "KXSOL-T200", "title": "Will SOL be above $200 on Dec 31, 2025?", "status": "active", "yes_bid": 40, "yes_ask": 44}
+                ]},
+                {"event_ticker": "KXPRES", "category": "Politics", "markets": [
+                    {"ticker": "KXPRES-50", "title": "Will turnout be above 50%?", "status": "active"}
+                ]}
+            ], "cursor": ""})
+            .to_string(),
+        ])
+        .await;
+
+        let catalog = MarketCatalog::discover(
+            &PolymarketClient::with_base_url(gamma_url),
+            &KalshiClient::with_base_url(kalshi_url),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(catalog.len(), 2);
+        assert_eq!(catalog.for_asset(Asset::Sol).len(), 2);
+        assert!(catalog.get(Venue::Polymarket, "sol-reach-250").is_some());
+        assert!(catalog.get(Venue::Kalshi, "KXSOL-T200").is_some());
+        assert!(catalog.get(Venue::Polymarket, "eth-above-5000").is_none());
+        let unmatched: Vec<&str> = catalog.unmatched().iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(unmatched, ["btc-dominance"]);
+    }
+}
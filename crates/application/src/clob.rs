@@ -0,0 +1,249 @@
+use crate::{AppError, TradeCalculation};
+use reqwest::Client;
+use serde::{Deserialize, Deserializer, Serialize};
+
+pub const CLOB_API_URL: &str = "https://clob.polymarket.com";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Level {
+    #[serde(deserialize_with = "decimal_string")]
+    pub price: f64,
+    #[serde(deserialize_with = "decimal_string")]
+    pub size: f64,
+}
+
+/// CLOB sends prices and sizes as decimal strings.
+pub(crate) fn decimal_string<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(serde::de::Error::custom)
+}
+
+/// Order book of one outcome token, best levels first on both sides.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Orderbook {
+    #[serde(default)]
+    pub asset_id: String,
+    #[serde(default)]
+    pub bids: Vec<Level>,
+    #[serde(default)]
+    pub asks: Vec<Level>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum FillTarget {
+    /// Spend up to this many dollars, fees included.
+    Usd(f64),
+    Shares(f64),
+}
+
+impl Orderbook {
+    pub(crate) fn sorted(mut self) -> Self {
+        self.bids.sort_by(|a, b| b.price.total_cmp(&a.price));
+        self.asks.sort_by(|a, b| a.price.total_cmp(&b.price));
+        self
+    }
+
+    pub fn best_bid(&self) -> Option<f64> {
+        self.bids.first().map(|level| level.price)
+    }
+
+    pub fn best_ask(&self) -> Option<f64> {
+        self.asks.first().map(|level| level.price)
+    }
+
+    pub fn midpoint(&self) -> Option<f64> {
+        match (self.best_bid(), self.best_ask()) {
+            (Some(bid), Some(ask)) => Some((bid + ask) / 2.0),
+            (None, Some(ask)) => Some(ask),
+            (Some(bid), None) => Some(bid),
+            (None, None) => None,
+        }
+    }
+
+    /// Walks the asks from the best price, buying until the target is met or
+    /// the book runs out, the last level partially. `fee_per_share` is added
+    /// to every level's price.
+    pub fn fill(&self, target: FillTarget, fee_per_share: f64) -> TradeCalculation {
+        let midpoint = self.midpoint().unwrap_or(0.0);
+        let mut shares = 0.0;
+        let mut spent = 0.0;
+        let (mut budget, mut wanted) = match target {
+            FillTarget::Usd(usd) => (usd, f64::INFINITY),
+            FillTarget::Shares(n) => (f64::INFINITY, n),
+        };
+
+        let mut exhausted = true;
+        for level in &self.asks {
+            let unit = level.price + fee_per_share;
+            let take = level.size.min(budget / unit).min(wanted).max(0.0);
+            shares += take;
+            spent += take * unit;
+            budget -= take * unit;
+            wanted -= take;
+            if wanted <= 0.0 || take < level.size {
+                exhausted = false;
+                break;
+            }
+        }
+
+        let price_per_share = if shares > 0.0 {
+            spent / shares - fee_per_share
+        } else {
+            0.0
+        };
+        let (unfilled_usd, unfilled_shares) = match target {
+            FillTarget::Usd(_) if exhausted => (budget, 0.0),
+            FillTarget::Usd(_) => (0.0, 0.0),
+            FillTarget::Shares(_) => (0.0, wanted.max(0.0)),
+        };
+
+        TradeCalculation {
+            estimated_cost: shares * (midpoint + fee_per_share),
+            price_per_share,
+            shares,
+            total_cost: spent,
+            midpoint,
+            slippage: if shares > 0.0 {
+                price_per_share - midpoint
+            } else {
+                0.0
+            },
+            unfilled_usd,
+            unfilled_shares,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ClobClient {
+    client: Client,
+    base_url: String,
+}
+
+impl Default for ClobClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClobClient {
+    pub fn new() -> Self {
+        Self::with_base_url(CLOB_API_URL)
+    }
+
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub async fn get_orderbook(&self, token_id: &str) -> Result<Orderbook, AppError> {
+        let response = self
+            .client
+            .get(format!("{}/book", self.base_url))
+            .query(&[("token_id", token_id)])
+            .header("accept", "application/json")
+            .send()
+            .await
+            .map_err(|_| AppError::PolymarketApiErr)?;
+
+        if !response.status().is_success() {
+            return Err(AppError::PolymarketApiErr);
+        }
+
+        let orderbook = response
+            .json::<Orderbook>()
+            .await
+            .map_err(|_| AppError::PolymarketApiErr)?;
+
+        Ok(orderbook.sorted())
+    }
+
+    pub async fn estimate_trade(
+        &self,
+        token_id: &str,
+        target: FillTarget,
+        fee_per_share: f64,
+    ) -> Result<TradeCalculation, AppError> {
+        Ok(self
+            .get_orderbook(token_id)
+            .await?
+            .fill(target, fee_per_share))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book() -> Orderbook {
+        Orderbook {
+            asset_id: "token".to_string(),
+            bids: vec![Level {
+                price: 0.38,
+                size: 100.0,
+            }],
+            asks: vec![
+                Level {
+                    price: 0.40,
+                    size: 10.0,
+                },
+                Level {
+                    price: 0.50,
+                    size: 10.0,
+                },
+            ],
+        }
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{a} != {b}");
+    }
+
+    #[test]
+    fn fills_share_count_across_levels() {
+        let trade = book().fill(FillTarget::Shares(15.0), 0.0);
+        assert_close(trade.shares, 15.0);
+        assert_close(trade.unfilled_shares, 0.0);
+        assert_close(trade.total_cost, 10.0 * 0.40 + 5.0 * 0.50);
+        assert_close(trade.midpoint, 0.39);
+        assert_close(trade.slippage, trade.price_per_share - 0.39);
+    }
+
+    #[test]
+    fn spends_usd_budget_including_fee() {
+        let trade = book().fill(FillTarget::Usd(5.0), 0.02);
+        assert_close(trade.shares, 10.0 + 0.8 / 0.52);
+        assert_close(trade.total_cost, 5.0);
+        assert_close(trade.unfilled_usd, 0.0);
+    }
+
+    #[test]
+    fn fills_fractions_of_a_share() {
+        let mut book = book();
+        book.asks[0].size = 2.5;
+        let trade = book.fill(FillTarget::Shares(3.25), 0.0);
+        assert_close(trade.shares, 3.25);
+        assert_close(trade.total_cost, 2.5 * 0.40 + 0.75 * 0.50);
+
+        let trade = book.fill(FillTarget::Usd(0.5), 0.0);
+        assert_close(trade.shares, 1.25);
+        assert_close(trade.price_per_share, 0.40);
+    }
+
+    #[test]
+    fn reports_what_the_book_cannot_fill() {
+        let by_shares = book().fill(FillTarget::Shares(25.0), 0.0);
+        assert_close(by_shares.shares, 20.0);
+        assert_close(by_shares.unfilled_shares, 5.0);
+
+        let by_usd = book().fill(FillTarget::Usd(100.0), 0.0);
+        assert_close(by_usd.shares, 20.0);
+        assert_close(by_usd.unfilled_usd, 100.0 - 9.0);
+    }
+}
@@ -0,0 +1,375 @@
+use crate::AppError;
+use crate::clob::{Level, Orderbook, decimal_string};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::{RwLock, broadcast, watch};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+pub const CLOB_WS_URL: &str = "wss://ws-subscriptions-clob.polymarket.com/ws/market";
+
+/// The market channel drops connections that stay silent for longer.
+const PING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Latest top of book and trade of one outcome token.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TokenQuote {
+    pub best_bid: Option<f64>,
+    pub best_ask: Option<f64>,
+    pub last_trade: Option<f64>,
+    pub last_updated: SystemTime,
+}
+
+impl Default for TokenQuote {
+    fn default() -> Self {
+        Self {
+            best_bid: None,
+            best_ask: None,
+            last_trade: None,
+            last_updated: SystemTime::UNIX_EPOCH,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QuoteUpdate {
+    pub token_id: String,
+    pub quote: TokenQuote,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Payload {
+    Many(Vec<MarketEvent>),
+    One(MarketEvent),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "event_type", rename_all = "snake_case")]
+enum MarketEvent {
+    Book {
+        asset_id: String,
+        #[serde(default, alias = "buys")]
+        bids: Vec<Level>,
+        #[serde(default, alias = "sells")]
+        asks: Vec<Level>,
+    },
+    PriceChange {
+        #[serde(default)]
+        price_changes: Vec<PriceChange>,
+    },
+    LastTradePrice {
+        asset_id: String,
+        #[serde(deserialize_with = "decimal_string")]
+        price: f64,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct PriceChange {
+    asset_id: String,
+    best_bid: Option<String>,
+    best_ask: Option<String>,
+}
+
+/// Live quotes from the Polymarket CLOB market channel.
+///
+/// [`ClobFeed::run`] keeps one WebSocket subscribed to the token ids in the
+/// `watch` channel, reconnecting with exponential backoff and resubscribing
+/// whenever the set changes. Quotes can be read with [`ClobFeed::quote`] or
+/// followed through [`ClobFeed::subscribe`].
+#[derive(Clone)]
+pub struct ClobFeed {
+    url: String,
+    quotes: Arc<RwLock<HashMap<String, TokenQuote>>>,
+    updates: broadcast::Sender<QuoteUpdate>,
+    pub min_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for ClobFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClobFeed {
+    pub fn new() -> Self {
+        Self::with_url(CLOB_WS_URL)
+    }
+
+    pub fn with_url(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            quotes: Arc::new(RwLock::new(HashMap::new())),
+            updates: broadcast::channel(256).0,
+            min_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+
+    pub async fn quote(&self, token_id: &str) -> Option<TokenQuote> {
+        self.quotes.read().await.get(token_id).copied()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<QuoteUpdate> {
+        self.updates.subscribe()
+    }
+
+    /// Runs until the `tokens` sender is dropped while there is nothing to
+    /// subscribe to, otherwise forever.
+    pub async fn run(&self, mut tokens: watch::Receiver<Vec<String>>) {
+        let mut delay = self.min_backoff;
+        loop {
+            let subscribed = tokens.borrow_and_update().clone();
+            if subscribed.is_empty() {
+                if tokens.changed().await.is_err() {
+                    return;
+                }
+                continue;
+            }
+
+            let mut received = false;
+            let result = self.session(&subscribed, &mut tokens, &mut received).await;
+            if received {
+                delay = self.min_backoff;
+            }
+            if result.is_err() {
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(self.max_backoff);
+            }
+        }
+    }
+
+    /// One connection. Returns `Ok` when the token set changed and the
+    /// caller should resubscribe, `Err` when the connection was lost.
+    async fn session(
+        &self,
+        tokens: &[String],
+        changes: &mut watch::Receiver<Vec<String>>,
+        received: &mut bool,
+    ) -> Result<(), AppError> {
+        let (mut socket, _) = connect_async(self.url.as_str())
+            .await
+            .map_err(|_| AppError::PolymarketApiErr)?;
+
+        let subscribe = serde_json::json!({ "assets_ids": tokens, "type": "market" });
+        socket
+            .send(Message::Text(subscribe.to_string()))
+            .await
+            .map_err(|_| AppError::PolymarketApiErr)?;
+
+        let mut ping =
+            tokio::time::interval_at(tokio::time::Instant::now() + PING_INTERVAL, PING_INTERVAL);
+        let mut watching = true;
+        loop {
+            tokio::select! {
+                message = socket.next() => match message {
+                    Some(Ok(Message::Text(text))) => {
+                        *received = true;
+                        self.apply(&text).await;
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
+                        return Err(AppError::PolymarketApiErr);
+                    }
+                    Some(Ok(_)) => {}
+                },
+                _ = ping.tick() => {
+                    socket
+                        .send(Message::Text("PING".to_string()))
+                        .await
+                        .map_err(|_| AppError::PolymarketApiErr)?;
+                }
+                changed = changes.changed(), if watching => match changed {
+                    Ok(()) => return Ok(()),
+                    // Nobody will change the set any more, keep the current one.
+                    Err(_) => watching = false,
+                },
+            }
+        }
+    }
+
+    async fn apply(&self, text: &str) {
+        let events = match serde_json::from_str::<Payload>(text) {
+            Ok(Payload::Many(events)) => events,
+            Ok(Payload::One(event)) => vec![event],
+            // "PONG" and anything we do not understand.
+            Err(_) => return,
+        };
+
+        let mut quotes = self.quotes.write().await;
+        let mut changed = Vec::new();
+        for event in events {
+            match event {
+                MarketEvent::Book {
+                    asset_id,
+                    bids,
+                    asks,
+                } => {
+                    let book = Orderbook {
+                        asset_id: asset_id.clone(),
+                        bids,
+                        asks,
+                    }
+                    .sorted();
+                    let quote = quotes.entry(asset_id.clone()).or_default();
+                    quote.best_bid = book.best_bid();
+                    quote.best_ask = book.best_ask();
+                    changed.push(asset_id);
+                }
+                MarketEvent::PriceChange { price_changes } => {
+                    for change in price_changes {
+                        // A side the change does not mention keeps its last price.
+                        let quote = quotes.entry(change.asset_id.clone()).or_default();
+                        if let Some(bid) = change.best_bid.and_then(|p| p.parse().ok()) {
+                            quote.best_bid = Some(bid);
+                        }
+                        if let Some(ask) = change.best_ask.and_then(|p| p.parse().ok()) {
+                            quote.best_ask = Some(ask);
+                        }
+                        changed.push(change.asset_id);
+                    }
+                }
+                MarketEvent::LastTradePrice { asset_id, price } => {
+                    quotes.entry(asset_id.clone()).or_default().last_trade = Some(price);
+                    changed.push(asset_id);
+                }
+                MarketEvent::Other => {}
+            }
+        }
+
+        let now = SystemTime::now();
+        for token_id in changed {
+            if let Some(quote) = quotes.get_mut(&token_id) {
+                quote.last_updated = now;
+                // No subscribers is not an error.
+                let _ = self.updates.send(QuoteUpdate {
+                    token_id,
+                    quote: *quote,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::accept_async;
+
+    async fn stand_in() -> (TcpListener, String) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        (listener, url)
+    }
+
+    fn feed(url: &str) -> ClobFeed {
+        ClobFeed {
+            min_backoff: Duration::from_millis(10),
+            ..ClobFeed::with_url(url)
+        }
+    }
+
+    async fn next_update(updates: &mut broadcast::Receiver<QuoteUpdate>) -> QuoteUpdate {
+        tokio::time::timeout(Duration::from_secs(5), updates.recv())
+            .await
+            .expect("no update in time")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn tracks_book_price_changes_and_trades() {
+        let (listener, url) = stand_in().await;
+        let feed = feed(&url);
+        let mut updates = feed.subscribe();
+        let (_tokens, rx) = watch::channel(vec!["no".to_string()]);
+        let runner = feed.clone();
+        tokio::spawn(async move { runner.run(rx).await });
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut socket = accept_async(stream).await.unwrap();
+        let subscribe = socket.next().await.unwrap().unwrap().into_text().unwrap();
+        assert!(subscribe.contains("\"no\""));
+
+        socket
+            .send(Message::Text(
+                r#"[{"event_type":"book","asset_id":"no","bids":[{"price":"0.60","size":"5"},{"price":"0.62","size":"1"}],"asks":[{"price":"0.66","size":"3"},{"price":"0.64","size":"2"}]}]"#
+                    .to_string(),
+            ))
+            .await
+            .unwrap();
+        let update = next_update(&mut updates).await;
+        assert_eq!(update.token_id, "no");
+        assert_eq!(update.quote.best_bid, Some(0.62));
+        assert_eq!(update.quote.best_ask, Some(0.64));
+
+        socket
+            .send(Message::Text(
+                r#"{"event_type":"price_change","price_changes":[{"asset_id":"no","price":"0.63","size":"10","side":"SELL","best_bid":"0.62","best_ask":"0.63"}]}"#
+                    .to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(next_update(&mut updates).await.quote.best_ask, Some(0.63));
+
+        socket
+            .send(Message::Text(
+                r#"{"event_type":"last_trade_price","asset_id":"no","price":"0.63","side":"BUY","size":"4"}"#
+                    .to_string(),
+            ))
+            .await
+            .unwrap();
+        next_update(&mut updates).await;
+        let quote = feed.quote("no").await.unwrap();
+        assert_eq!(quote.last_trade, Some(0.63));
+        assert_eq!(quote.best_bid, Some(0.62));
+    }
+
+    #[tokio::test]
+    async fn reconnects_and_resubscribes() {
+        let (listener, url) = stand_in().await;
+        let feed = feed(&url);
+        let (tokens, rx) = watch::channel(vec!["a".to_string()]);
+        let runner = feed.clone();
+        tokio::spawn(async move { runner.run(rx).await });
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut socket = accept_async(stream).await.unwrap();
+        socket.next().await.unwrap().unwrap();
+        socket.close(None).await.unwrap();
+        drop(socket);
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut socket = accept_async(stream).await.unwrap();
+        let subscribe = socket.next().await.unwrap().unwrap().into_text().unwrap();
+        assert!(subscribe.contains("\"a\""));
+
+        tokens.send(vec!["a".to_string(), "b".to_string()]).unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut socket = accept_async(stream).await.unwrap();
+        let subscribe = socket.next().await.unwrap().unwrap().into_text().unwrap();
+        assert!(subscribe.contains("\"b\""));
+    }
+
+    #[tokio::test]
+    async fn price_changes_keep_sides_they_do_not_mention() {
+        let feed = ClobFeed::with_url("ws://127.0.0.1:9");
+        feed.apply(
+            r#"[{"event_type":"book","asset_id":"no","bids":[{"price":"0.60","size":"5"}],"asks":[{"price":"0.64","size":"2"}]}]"#,
+        )
+        .await;
+        feed.apply(
+            r#"{"event_type":"price_change","price_changes":[{"asset_id":"no","price":"0.61","size":"3","side":"BUY","best_bid":"0.61"}]}"#,
+        )
+        .await;
+
+        let quote = feed.quote("no").await.unwrap();
+        assert_eq!(quote.best_bid, Some(0.61));
+        assert_eq!(quote.best_ask, Some(0.64));
+    }
+}
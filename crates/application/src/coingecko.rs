@@ -0,0 +1,240 @@
+use crate::AppError;
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+pub const COINGECKO_API_URL: &str = "https://api.coingecko.com/api/v3";
+
+#[derive(Clone)]
+pub struct CoinGeckoClient {
+    client: Client,
+    base_url: String,
+}
+
+impl Default for CoinGeckoClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CoinGeckoClient {
+    pub fn new() -> Self {
+        Self::with_base_url(COINGECKO_API_URL)
+    }
+
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// USD prices of all `ids` in one `simple/price` request, keyed by id.
+    /// Ids CoinGecko does not know or has no USD price for are missing from
+    /// the result.
+    pub async fn simple_price(&self, ids: &[&str]) -> Result<HashMap<String, f64>, AppError> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let url = format!("{}/simple/price", self.base_url);
+        let prices: HashMap<String, Quotes> = self
+            .get_json(
+                &url,
+                &[("ids", ids.join(",").as_str()), ("vs_currencies", "usd")],
+            )
+            .await?;
+
+        Ok(usd_prices(prices))
+    }
+
+    /// USD prices of token contracts on one platform (`solana`, `ethereum`,
+    /// `arbitrum-one`, ...), keyed by address as CoinGecko returns it.
+    pub async fn token_price(
+        &self,
+        platform: &str,
+        addresses: &[&str],
+    ) -> Result<HashMap<String, f64>, AppError> {
+        if addresses.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let url = format!("{}/simple/token_price/{}", self.base_url, platform);
+        let prices: HashMap<String, Quotes> = self
+            .get_json(
+                &url,
+                &[
+                    ("contract_addresses", addresses.join(",").as_str()),
+                    ("vs_currencies", "usd"),
+                ],
+            )
+            .await?;
+
+        Ok(usd_prices(prices))
+    }
+
+    /// USD price history of a coin as `(unix milliseconds, price)`, hourly
+    /// for 2 to 90 days, daily beyond.
+    pub async fn market_chart(&self, id: &str, days: u32) -> Result<Vec<(i64, f64)>, AppError> {
+        let url = format!("{}/coins/{}/market_chart", self.base_url, id);
+        let chart: MarketChart = self
+            .get_json(
+                &url,
+                &[("vs_currency", "usd"), ("days", days.to_string().as_str())],
+            )
+            .await?;
+
+        Ok(chart
+            .prices
+            .into_iter()
+            .map(|(time, price)| (time as i64, price))
+            .collect())
+    }
+
+    /// Id of the highest ranked coin whose ticker is exactly `symbol`.
+    pub async fn find_coin_id(&self, symbol: &str) -> Result<Option<String>, AppError> {
+        let url = format!("{}/search", self.base_url);
+        let found: SearchResponse = self.get_json(&url, &[("query", symbol)]).await?;
+
+        Ok(found
+            .coins
+            .into_iter()
+            .find(|coin| coin.symbol.eq_ignore_ascii_case(symbol))
+            .map(|coin| coin.id))
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+        query: &[(&str, &str)],
+    ) -> Result<T, AppError> {
+        let response = self
+            .client
+            .get(url)
+            .query(query)
+            .header("accept", "application/json")
+            .send()
+            .await
+            .map_err(|_| AppError::ExchangePriceApiErr)?;
+
+        if !response.status().is_success() {
+            return Err(AppError::ExchangePriceApiErr);
+        }
+
+        response
+            .json::<T>()
+            .await
+            .map_err(|_| AppError::ExchangePriceApiErr)
+    }
+}
+
+/// Coin id by uppercase symbol, searched for the first time a symbol is
+/// seen. `None` when the search found nothing.
+#[derive(Debug, Default)]
+pub struct CoinIds(Mutex<BTreeMap<String, Option<String>>>);
+
+impl CoinIds {
+    pub fn with_coins(
+        coins: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Self {
+        let ids = coins
+            .into_iter()
+            .map(|(symbol, id)| (symbol.into().to_uppercase(), Some(id.into())))
+            .collect();
+        Self(Mutex::new(ids))
+    }
+
+    pub async fn resolve(
+        &self,
+        client: &CoinGeckoClient,
+        symbol: &str,
+    ) -> Result<Option<String>, AppError> {
+        if let Some(id) = self.0.lock().unwrap().get(symbol) {
+            return Ok(id.clone());
+        }
+        let id = client.find_coin_id(symbol).await?;
+        self.0
+            .lock()
+            .unwrap()
+            .insert(symbol.to_string(), id.clone());
+        Ok(id)
+    }
+}
+
+/// Quotes of one id by currency, read loosely: a `null` or malformed price
+/// loses that id, not the whole response.
+type Quotes = HashMap<String, serde_json::Value>;
+
+fn usd_prices(prices: HashMap<String, Quotes>) -> HashMap<String, f64> {
+    prices
+        .into_iter()
+        .filter_map(|(id, quotes)| Some((id, quotes.get("usd")?.as_f64()?)))
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct MarketChart {
+    prices: Vec<(f64, f64)>,
+}
+
+/// `/search` lists coins by market cap rank.
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    #[serde(default)]
+    coins: Vec<SearchCoin>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchCoin {
+    id: String,
+    symbol: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stand_in;
+
+    #[tokio::test]
+    async fn prices_every_id_in_one_request() {
+        let (url, server) = stand_in::serve_json(vec![
+            r#"{"solana": {"usd": 180.5}, "bitcoin": {"usd": 110000}, "meteora": {}, "broken": {"usd": null}}"#
+                .to_string(),
+        ])
+        .await;
+
+        let prices = CoinGeckoClient::with_base_url(url)
+            .simple_price(&["solana", "bitcoin", "meteora", "broken", "unknown"])
+            .await
+            .unwrap();
+
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices["solana"], 180.5);
+        assert_eq!(prices["bitcoin"], 110_000.0);
+
+        let requests = server.await.unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with(
+            "GET /simple/price?ids=solana%2Cbitcoin%2Cmeteora%2Cbroken%2Cunknown&vs_currencies=usd "
+        ));
+    }
+
+    #[tokio::test]
+    async fn no_ids_no_request() {
+        let client = CoinGeckoClient::with_base_url("http://127.0.0.1:9");
+        assert!(client.simple_price(&[]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_requests_are_errors() {
+        let stand_in = stand_in::StandIn::bind().await;
+        let client = CoinGeckoClient::with_base_url(&stand_in.url);
+        let _server = stand_in.serve(vec![(429, String::new())]);
+
+        assert!(matches!(
+            client.simple_price(&["solana"]).await,
+            Err(AppError::ExchangePriceApiErr)
+        ));
+    }
+}
@@ -0,0 +1,236 @@
+use crate::history::PricePoint;
+use crate::market::{Asset, Market, Venue};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+/// How a market's YES price moved with an asset over the tracker window.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MarketCorrelation {
+    pub venue: Venue,
+    pub market_id: String,
+    pub question: String,
+    pub asset: Asset,
+    /// Pearson correlation of YES price changes with the asset's log returns.
+    pub correlation: f64,
+    /// YES price change per unit of log return, `0.5` moves YES by 5 cents
+    /// when the asset gains 10%.
+    pub beta: f64,
+    /// Intervals both series moved in.
+    pub samples: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Series {
+    question: String,
+    points: Vec<PricePoint>,
+}
+
+/// YES prices of tracked markets over time, correlated with spot prices on
+/// demand.
+///
+/// Both series are sampled on `interval` buckets, the last price in a bucket
+/// counting, and only changes between buckets present in both are compared.
+#[derive(Debug, Clone)]
+pub struct CorrelationTracker {
+    series: HashMap<(Venue, String), Series>,
+    pub window: Duration,
+    pub interval: Duration,
+    pub min_samples: usize,
+}
+
+impl Default for CorrelationTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CorrelationTracker {
+    pub fn new() -> Self {
+        Self {
+            series: HashMap::new(),
+            window: Duration::from_secs(7 * 24 * 60 * 60),
+            interval: Duration::from_secs(60 * 60),
+            min_samples: 24,
+        }
+    }
+
+    /// Snapshots the YES price of every market that has one and forgets
+    /// prices older than `window`.
+    pub fn record<'a>(&mut self, markets: impl IntoIterator<Item = &'a Market>, now: SystemTime) {
+        let time = unix_ms(now);
+        for market in markets {
+            let Some(usd) = market.yes_price() else {
+                continue;
+            };
+            self.series
+                .entry((market.venue, market.id.clone()))
+                .or_insert_with(|| Series {
+                    question: market.question.clone(),
+                    points: Vec::new(),
+                })
+                .points
+                .push(PricePoint { time, usd });
+        }
+
+        let oldest = time - self.window.as_millis() as i64;
+        for series in self.series.values_mut() {
+            series.points.retain(|point| point.time >= oldest);
+        }
+        self.series.retain(|_, series| !series.points.is_empty());
+    }
+
+    pub fn len(&self) -> usize {
+        self.series.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.series.is_empty()
+    }
+
+    /// Every market with at least `min_samples` common moves, strongest
+    /// correlation first. `spot` are the asset's prices, oldest first.
+    pub fn correlations(&self, asset: Asset, spot: &[PricePoint]) -> Vec<MarketCorrelation> {
+        let width = (self.interval.as_millis() as i64).max(1);
+        let spot = buckets(spot, width);
+
+        let mut correlations: Vec<MarketCorrelation> = self
+            .series
+            .iter()
+            .filter_map(|((venue, market_id), series)| {
+                let yes = buckets(&series.points, width);
+                let (returns, changes): (Vec<f64>, Vec<f64>) = paired_moves(&spot, &yes).unzip();
+                if returns.len() < self.min_samples {
+                    return None;
+                }
+                let (correlation, beta) = regression(&returns, &changes)?;
+                Some(MarketCorrelation {
+                    venue: *venue,
+                    market_id: market_id.clone(),
+                    question: series.question.clone(),
+                    asset,
+                    correlation,
+                    beta,
+                    samples: returns.len(),
+                })
+            })
+            .collect();
+
+        correlations.sort_by(|a, b| b.correlation.abs().total_cmp(&a.correlation.abs()));
+        correlations
+    }
+}
+
+fn unix_ms(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+/// Last price in every `width` bucket, keyed by bucket start.
+fn buckets(points: &[PricePoint], width: i64) -> BTreeMap<i64, f64> {
+    points
+        .iter()
+        .map(|point| (point.time - point.time.rem_euclid(width), point.usd))
+        .collect()
+}
+
+/// Spot log return and YES change between consecutive buckets both series
+/// have a price in.
+fn paired_moves<'a>(
+    spot: &'a BTreeMap<i64, f64>,
+    yes: &'a BTreeMap<i64, f64>,
+) -> impl Iterator<Item = (f64, f64)> + 'a {
+    let common: Vec<(f64, f64)> = spot
+        .iter()
+        .filter_map(|(bucket, price)| Some((*price, *yes.get(bucket)?)))
+        .filter(|(price, _)| *price > 0.0)
+        .collect();
+    (1..common.len()).map(move |i| {
+        let (spot_before, yes_before) = common[i - 1];
+        let (spot_after, yes_after) = common[i];
+        ((spot_after / spot_before).ln(), yes_after - yes_before)
+    })
+}
+
+/// Correlation of `xs` and `ys` and the slope of `ys` on `xs`. `None` when
+/// either does not move.
+fn regression(xs: &[f64], ys: &[f64]) -> Option<(f64, f64)> {
+    let n = xs.len() as f64;
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in xs.iter().zip(ys) {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    if var_x <= 0.0 || var_y <= 0.0 {
+        return None;
+    }
+    Some((cov / (var_x * var_y).sqrt(), cov / var_x))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 60 * 60 * 1000;
+
+    #[test]
+    fn finds_markets_moving_with_the_asset() {
+        let mut tracker = CorrelationTracker::new();
+        tracker.min_samples = 5;
+
+        let mut spot = Vec::new();
+        let mut price = 100.0;
+        for hour in 0..30i64 {
+            let r = if hour % 3 == 0 { -0.02 } else { 0.015 };
+            price *= f64::exp(r);
+            spot.push(PricePoint {
+                time: hour * HOUR,
+                usd: price,
+            });
+            let follows = 0.5 + 0.5 * (price / 100.0).ln();
+            let opposes = 0.5 - 0.2 * (price / 100.0).ln();
+            let noise = if hour % 2 == 0 { 0.41 } else { 0.42 };
+            tracker.record(
+                [
+                    &Market::binary("follows", "Question follows?", follows),
+                    &Market::binary("opposes", "Question opposes?", opposes),
+                    &Market::binary("noise", "Question noise?", noise),
+                ],
+                UNIX_EPOCH + Duration::from_millis((hour * HOUR + 1000) as u64),
+            );
+        }
+
+        let correlations = tracker.correlations(Asset::Sol, &spot);
+        let by_id = |id: &str| {
+            correlations
+                .iter()
+                .find(|c| c.market_id == id)
+                .unwrap()
+                .clone()
+        };
+        assert!((by_id("follows").correlation - 1.0).abs() < 1e-9);
+        assert!((by_id("follows").beta - 0.5).abs() < 1e-9);
+        assert!((by_id("opposes").correlation + 1.0).abs() < 1e-9);
+        assert!((by_id("opposes").beta + 0.2).abs() < 1e-9);
+        assert!(by_id("noise").correlation.abs() < 0.5);
+        assert_eq!(by_id("follows").samples, 29);
+    }
+
+    #[test]
+    fn forgets_old_prices_and_skips_short_series() {
+        let mut tracker = CorrelationTracker::new();
+        tracker.record([&Market::binary("a", "Question a?", 0.3)], UNIX_EPOCH);
+        tracker.record(
+            [&Market::binary("b", "Question b?", 0.3)],
+            UNIX_EPOCH + tracker.window + Duration::from_secs(1),
+        );
+
+        assert_eq!(tracker.len(), 1);
+        assert!(tracker.correlations(Asset::Sol, &[]).is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+use crate::hedge::PriceRange;
+use crate::history::PricePoint;
+use serde::Serialize;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+pub struct ForecastPoint {
+    /// Unix milliseconds.
+    pub time: i64,
+    pub usd: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Forecast {
+    pub history: Vec<PricePoint>,
+    pub predicted: Vec<ForecastPoint>,
+    /// EMA over `history` followed by `predicted`, one value per point.
+    pub ema: Vec<f64>,
+    /// Standard deviation of the one step ahead errors over `history`.
+    pub residual_std: f64,
+}
+
+impl Forecast {
+    /// Prediction interval at the last forecast step.
+    pub fn band(&self) -> Option<PriceRange> {
+        let last = self.predicted.last()?;
+        Some(PriceRange {
+            low: last.lower,
+            high: last.upper,
+        })
+    }
+}
+
+/// Rolling window linear regression with an EMA overlay, what the price
+/// chart draws.
+///
+/// Each step fits a line through the last `window` values, predicted ones
+/// included, and extends it by one. The band around a prediction `h` steps
+/// ahead is `z * residual_std * sqrt(h)`, with `residual_std` measured by
+/// running the same regression over the history.
+pub struct Forecaster {
+    pub window: usize,
+    pub ema_period: usize,
+    /// Normal quantile of the band, `1.96` for 95%.
+    pub z: f64,
+}
+
+impl Default for Forecaster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Forecaster {
+    pub fn new() -> Self {
+        Self {
+            window: 6,
+            ema_period: 5,
+            z: 1.96,
+        }
+    }
+
+    /// `None` with fewer than two points of history.
+    pub fn forecast(
+        &self,
+        history: &[PricePoint],
+        step: Duration,
+        count: usize,
+    ) -> Option<Forecast> {
+        if history.len() < 2 {
+            return None;
+        }
+        let last = history.last()?;
+        let values: Vec<f64> = history.iter().map(|point| point.usd).collect();
+        let residual_std = residual_std(&values, self.window);
+        let step = step.as_millis() as i64;
+
+        let predicted: Vec<ForecastPoint> = local_regression(&values, self.window, count)
+            .into_iter()
+            .enumerate()
+            .map(|(i, usd)| {
+                let ahead = i + 1;
+                let spread = self.z * residual_std * (ahead as f64).sqrt();
+                ForecastPoint {
+                    time: last.time + ahead as i64 * step,
+                    usd,
+                    lower: (usd - spread).max(0.0),
+                    upper: usd + spread,
+                }
+            })
+            .collect();
+
+        let all: Vec<f64> = values
+            .iter()
+            .copied()
+            .chain(predicted.iter().map(|point| point.usd))
+            .collect();
+
+        Some(Forecast {
+            history: history.to_vec(),
+            predicted,
+            ema: ema(&all, self.ema_period),
+            residual_std,
+        })
+    }
+}
+
+/// `count` values past the end of `values`, each from a line fitted through
+/// the `window` values before it.
+pub fn local_regression(values: &[f64], window: usize, count: usize) -> Vec<f64> {
+    let mut result = values.to_vec();
+    for _ in 0..count {
+        let start = result.len().saturating_sub(window);
+        result.push(next_value(&result[start..]));
+    }
+    result.split_off(values.len())
+}
+
+/// Least squares line through `ys` at `x = 0, 1, ...`, evaluated one step
+/// past the end.
+fn next_value(ys: &[f64]) -> f64 {
+    let n = ys.len() as f64;
+    if ys.len() < 2 {
+        return ys.last().copied().unwrap_or(0.0);
+    }
+    let sum_x: f64 = (0..ys.len()).map(|x| x as f64).sum();
+    let sum_y: f64 = ys.iter().sum();
+    let sum_xy: f64 = ys.iter().enumerate().map(|(x, y)| x as f64 * y).sum();
+    let sum_x2: f64 = (0..ys.len()).map(|x| (x * x) as f64).sum();
+
+    let slope = (n * sum_xy - sum_x * sum_y) / (n * sum_x2 - sum_x * sum_x);
+    let intercept = (sum_y - slope * sum_x) / n;
+    slope * n + intercept
+}
+
+/// Seeded with the first value, `alpha = 2 / (period + 1)`.
+pub fn ema(values: &[f64], period: usize) -> Vec<f64> {
+    let alpha = 2.0 / (period as f64 + 1.0);
+    let mut result: Vec<f64> = Vec::with_capacity(values.len());
+    for value in values {
+        let next = match result.last() {
+            Some(previous) => alpha * value + (1.0 - alpha) * previous,
+            None => *value,
+        };
+        result.push(next);
+    }
+    result
+}
+
+fn residual_std(values: &[f64], window: usize) -> f64 {
+    let window = window.max(2);
+    let errors: Vec<f64> = (window..values.len())
+        .map(|i| values[i] - next_value(&values[i - window..i]))
+        .collect();
+    if errors.is_empty() {
+        return 0.0;
+    }
+    (errors.iter().map(|e| e * e).sum::<f64>() / errors.len() as f64).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(prices: &[f64]) -> Vec<PricePoint> {
+        prices
+            .iter()
+            .enumerate()
+            .map(|(i, usd)| PricePoint {
+                time: i as i64 * 1000,
+                usd: *usd,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn extends_a_straight_line() {
+        let predicted = local_regression(&[1.0, 2.0, 3.0, 4.0], 3, 3);
+        assert_eq!(predicted, [5.0, 6.0, 7.0]);
+    }
+
+    #[test]
+    fn ema_matches_the_chart() {
+        let values = ema(&[10.0, 13.0, 16.0], 5);
+        assert_eq!(values[0], 10.0);
+        assert_eq!(values[1], 11.0);
+        assert!((values[2] - 38.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn band_widens_with_noise_and_distance() {
+        let forecaster = Forecaster::new();
+        let smooth = forecaster
+            .forecast(
+                &history(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]),
+                Duration::from_secs(1),
+                3,
+            )
+            .unwrap();
+        assert_eq!(smooth.residual_std, 0.0);
+        assert_eq!(smooth.predicted[2].time, 10_000);
+        assert_eq!(smooth.ema.len(), 11);
+
+        let noisy = forecaster
+            .forecast(
+                &history(&[10.0, 12.0, 9.0, 13.0, 8.0, 12.0, 10.0, 14.0, 9.0]),
+                Duration::from_secs(1),
+                3,
+            )
+            .unwrap();
+        let width = |point: &ForecastPoint| point.upper - point.lower;
+        assert!(noisy.residual_std > 0.0);
+        assert!(width(&noisy.predicted[2]) > width(&noisy.predicted[0]));
+        assert_eq!(noisy.band().unwrap().high, noisy.predicted[2].upper);
+    }
+
+    #[test]
+    fn needs_two_points() {
+        assert!(
+            Forecaster::new()
+                .forecast(&history(&[1.0]), Duration::from_secs(1), 3)
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,485 @@
+use crate::AppError;
+use crate::AssetsRow;
+use crate::catalog::MarketCatalog;
+use crate::market::{Asset, Market};
+use crate::prices::PriceRegistry;
+use crate::sizing::{self, HedgeSize, ScenarioPoint, SizingInput};
+use crate::terms::{Confidence, Direction, MarketTerms, Settlement};
+use crate::volatility::{Edge, VolatilityModel};
+use crate::zerion::PositionType;
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+/// Spot position the engine hedges, valued at the current exchange rate.
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+pub struct Holding {
+    pub asset: Asset,
+    pub quantity: f64,
+    pub spot_price: f64,
+}
+
+impl Holding {
+    pub fn value(&self) -> f64 {
+        self.quantity * self.spot_price
+    }
+
+    /// Keeps the rows of assets we have both a market and a price for, one
+    /// holding per asset over every chain it is held on. Loans are debts,
+    /// not something to hedge.
+    pub fn from_assets(rows: &[AssetsRow], prices: &PriceRegistry) -> Vec<Holding> {
+        let mut quantities: BTreeMap<Asset, f64> = BTreeMap::new();
+        for row in rows {
+            if row.position_type == Some(PositionType::Loan) {
+                continue;
+            }
+            let (Ok(asset), Ok(quantity)) =
+                (Asset::from_str(&row.asset), row.balance.parse::<f64>())
+            else {
+                continue;
+            };
+            *quantities.entry(asset).or_default() += quantity;
+        }
+
+        quantities
+            .into_iter()
+            .filter_map(|(asset, quantity)| {
+                let spot_price = prices.get_price(asset.symbol())?;
+                (quantity > 0.0 && spot_price > 0.0).then_some(Holding {
+                    asset,
+                    quantity,
+                    spot_price,
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub enum Side {
+    Yes,
+    No,
+}
+
+impl Side {
+    /// The side that pays when the underlying falls below the strike, if
+    /// any. The NO of a barrier above ("reach $X") pays only if the price
+    /// never touches the strike, which says nothing about a fall under it.
+    pub fn hedging(terms: &MarketTerms) -> Option<Self> {
+        match (terms.settlement, terms.direction) {
+            (_, Direction::Below) => Some(Side::Yes),
+            (Settlement::AtExpiry, Direction::Above) => Some(Side::No),
+            (Settlement::Barrier, Direction::Above) => None,
+        }
+    }
+
+    pub fn outcome(&self) -> &'static str {
+        match self {
+            Side::Yes => "Yes",
+            Side::No => "No",
+        }
+    }
+}
+
+/// Underlying prices at which the hedge pays out.
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+pub struct PriceRange {
+    pub low: f64,
+    pub high: f64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct HedgeSuggestion {
+    pub market: Market,
+    pub terms: MarketTerms,
+    pub holding: Holding,
+    pub side: Side,
+    /// Quoted share price, without the venue fee.
+    pub share_price: f64,
+    pub shares: f64,
+    pub stake: f64,
+    pub max_payout: f64,
+    /// Stake as a percentage of the hedged position value.
+    pub cost_pct: f64,
+    pub protected_range: PriceRange,
+    /// Where the price forecast expects the asset, see [`HedgeEngine::forecast_bands`].
+    pub forecast_band: Option<PriceRange>,
+    /// Model probability of `side` against its price, see [`HedgeEngine::volatility`].
+    pub edge: Option<Edge>,
+}
+
+/// Matches long spot holdings with markets that pay when the asset falls.
+///
+/// A "below" market is bought on YES and an "above" market settling at expiry
+/// on NO, in both cases only for strikes under the current spot price. "Reach"
+/// markets are skipped, see [`Side::hedging`]. The stake covers
+/// `coverage` of the loss between spot and strike, see [`sizing::size_hedge`],
+/// and suggestions are ranked by cost.
+pub struct HedgeEngine {
+    pub max_suggestions: usize,
+    pub coverage: f64,
+    /// Lowest strike considered, as a fraction of spot.
+    pub min_strike_ratio: f64,
+    pub min_confidence: Confidence,
+    /// Forecast prediction interval per asset, attached to its suggestions.
+    pub forecast_bands: HashMap<Asset, PriceRange>,
+    /// Spot and realized volatility the suggestion edges are computed from.
+    pub volatility: VolatilityModel,
+}
+
+impl Default for HedgeEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HedgeEngine {
+    pub fn new() -> Self {
+        Self {
+            max_suggestions: 5,
+            coverage: 1.0,
+            min_strike_ratio: 0.5,
+            min_confidence: Confidence::Medium,
+            forecast_bands: HashMap::new(),
+            volatility: VolatilityModel::new(),
+        }
+    }
+
+    pub fn suggest(&self, holdings: &[Holding], catalog: &MarketCatalog) -> Vec<HedgeSuggestion> {
+        let now = Utc::now();
+        let mut suggestions: Vec<HedgeSuggestion> = holdings
+            .iter()
+            .flat_map(|holding| {
+                catalog
+                    .for_asset(holding.asset)
+                    .iter()
+                    .filter(|entry| entry.terms.confidence >= self.min_confidence)
+                    .filter(|entry| entry.terms.expiry.is_none_or(|expiry| expiry > now))
+                    .filter_map(|entry| self.evaluate(holding, &entry.market, &entry.terms))
+            })
+            .collect();
+
+        suggestions.sort_by(|a, b| a.cost_pct.total_cmp(&b.cost_pct));
+        suggestions.truncate(self.max_suggestions);
+        suggestions
+    }
+
+    fn evaluate(
+        &self,
+        holding: &Holding,
+        market: &Market,
+        terms: &MarketTerms,
+    ) -> Option<HedgeSuggestion> {
+        let side = Side::hedging(terms)?;
+        let share_price = market.outcome_price(side.outcome())?;
+        if terms.strike < holding.spot_price * self.min_strike_ratio {
+            return None;
+        }
+
+        let size = sizing::size_hedge(&SizingInput {
+            position_usd: holding.value(),
+            spot_price: holding.spot_price,
+            strike: terms.strike,
+            coverage: self.coverage,
+            share_price,
+            fee_per_share: sizing::fee_per_share(market.venue, share_price),
+            direction: terms.direction,
+            settlement: terms.settlement,
+            side,
+        })
+        .ok()?;
+
+        Some(HedgeSuggestion {
+            market: market.clone(),
+            terms: terms.clone(),
+            holding: *holding,
+            side,
+            share_price,
+            shares: size.shares,
+            stake: size.stake,
+            max_payout: size.max_payout,
+            cost_pct: size.stake / holding.value() * 100.0,
+            protected_range: PriceRange {
+                low: 0.0,
+                high: terms.strike,
+            },
+            forecast_band: self.forecast_bands.get(&holding.asset).copied(),
+            edge: self.volatility.edge(market, terms, side),
+        })
+    }
+}
+
+/// Number of intervals in the scenario price grid.
+const SCENARIO_STEPS: usize = 20;
+/// The grid spans this fraction of spot on each side.
+const SCENARIO_SPAN: f64 = 0.5;
+
+/// Combined spot and hedge P&L at expiry for one holding and one market.
+/// Barrier markets get a row for the strike touched and one for it not
+/// touched wherever both can happen.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct HedgeScenario {
+    pub holding: Holding,
+    pub market: Market,
+    pub terms: MarketTerms,
+    pub side: Side,
+    pub share_price: f64,
+    pub size: HedgeSize,
+    pub points: Vec<ScenarioPoint>,
+    pub at_spot: ScenarioPoint,
+    pub breakevens: Vec<f64>,
+}
+
+impl HedgeScenario {
+    pub fn build(
+        holding: Holding,
+        market: Market,
+        terms: MarketTerms,
+        coverage: f64,
+    ) -> Result<Self, AppError> {
+        if terms.asset != holding.asset {
+            return Err(AppError::HedgeSizingErr(format!(
+                "market is on {}, position is {}",
+                terms.asset, holding.asset
+            )));
+        }
+
+        let side = Side::hedging(&terms).ok_or_else(|| {
+            AppError::HedgeSizingErr("market does not pay when the price falls".to_string())
+        })?;
+        let share_price = market.outcome_price(side.outcome()).ok_or_else(|| {
+            AppError::HedgeSizingErr(format!("market has no {} price", side.outcome()))
+        })?;
+        let input = SizingInput {
+            position_usd: holding.value(),
+            spot_price: holding.spot_price,
+            strike: terms.strike,
+            coverage,
+            share_price,
+            fee_per_share: sizing::fee_per_share(market.venue, share_price),
+            direction: terms.direction,
+            settlement: terms.settlement,
+            side,
+        };
+        let size = sizing::size_hedge(&input)?;
+
+        let mut prices = sizing::price_grid(
+            holding.spot_price * (1.0 - SCENARIO_SPAN),
+            holding.spot_price * (1.0 + SCENARIO_SPAN),
+            SCENARIO_STEPS,
+        );
+        prices.push(terms.strike);
+        prices.sort_by(f64::total_cmp);
+        prices.dedup();
+
+        let points = sizing::scenario_grid(&input, &size, &prices);
+        // Nothing has moved yet, a barrier is not touched.
+        let at_spot = *sizing::scenario_grid(&input, &size, &[holding.spot_price])
+            .last()
+            .expect("one point per price");
+        let breakevens = sizing::breakevens(&input, &size);
+
+        Ok(Self {
+            holding,
+            market,
+            terms,
+            side,
+            share_price,
+            size,
+            points,
+            at_spot,
+            breakevens,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oracle::AggregatedPrice;
+    use crate::prices::{AssetKey, PriceUpdate};
+
+    const SPOT: f64 = 200.0;
+
+    fn registry(prices: &[(&str, f64)]) -> PriceRegistry {
+        let mut registry = PriceRegistry::new();
+        registry.record(PriceUpdate {
+            prices: prices
+                .iter()
+                .map(|(symbol, usd)| {
+                    let price = AggregatedPrice {
+                        usd: *usd,
+                        sources: vec!["test".to_string()],
+                        dropped: Vec::new(),
+                    };
+                    (AssetKey::symbol(symbol), price)
+                })
+                .collect(),
+            ..PriceUpdate::default()
+        });
+        registry
+    }
+
+    fn row(asset: &str, balance: &str, position_type: Option<PositionType>) -> AssetsRow {
+        AssetsRow {
+            asset: asset.to_string(),
+            balance: balance.to_string(),
+            value: String::new(),
+            key: None,
+            mint: None,
+            name: None,
+            decimals: None,
+            logo_uri: None,
+            chain: None,
+            position_type,
+            price: None,
+        }
+    }
+
+    fn terms(question: &str) -> MarketTerms {
+        MarketTerms::parse(question, "", "", None).unwrap()
+    }
+
+    fn sol(quantity: f64) -> Holding {
+        Holding {
+            asset: Asset::Sol,
+            quantity,
+            spot_price: SPOT,
+        }
+    }
+
+    fn catalog() -> MarketCatalog {
+        MarketCatalog::from_markets([
+            Market::binary(
+                "sol-dip-150",
+                "Will SOL dip to $150 by December 31, 2099?",
+                0.2,
+            ),
+            Market::binary(
+                "sol-above-160",
+                "Will SOL be above $160 on December 31, 2099?",
+                0.85,
+            ),
+            Market::binary(
+                "sol-reach-180",
+                "Will SOL reach $180 by December 31, 2099?",
+                0.9,
+            ),
+            Market::binary(
+                "sol-dip-80",
+                "Will SOL dip to $80 by December 31, 2099?",
+                0.03,
+            ),
+            Market::binary(
+                "btc-dip-90k",
+                "Will BTC dip to $90,000 by December 31, 2099?",
+                0.3,
+            ),
+        ])
+    }
+
+    #[test]
+    fn picks_the_side_that_pays_on_a_fall() {
+        let cases = [
+            ("Will SOL dip to $150 by December 31?", Some(Side::Yes)),
+            ("Will SOL be below $150 on December 31?", Some(Side::Yes)),
+            ("Will SOL be above $150 on December 31?", Some(Side::No)),
+            ("Will SOL reach $150 by December 31?", None),
+        ];
+        for (question, side) in cases {
+            assert_eq!(Side::hedging(&terms(question)), side, "{question}");
+        }
+    }
+
+    #[test]
+    fn sums_holdings_over_chains() {
+        let rows = [
+            row("SOL", "2", Some(PositionType::Wallet)),
+            row("SOL", "1.5", Some(PositionType::Staked)),
+            row("SOL", "10", Some(PositionType::Loan)),
+            row("ETH", "1", None),
+            row("BONK", "1000000", None),
+            row("BTC", "0", None),
+        ];
+        let holdings = Holding::from_assets(&rows, &registry(&[("SOL", SPOT), ("BTC", 90_000.0)]));
+
+        // ETH has no price, BONK no market and the BTC row is empty.
+        assert_eq!(holdings.len(), 1);
+        assert_eq!(holdings[0].asset, Asset::Sol);
+        assert_eq!(holdings[0].quantity, 3.5);
+        assert_eq!(holdings[0].value(), 700.0);
+    }
+
+    #[test]
+    fn suggests_markets_that_pay_on_a_fall() {
+        let suggestions = HedgeEngine::new().suggest(&[sol(10.0)], &catalog());
+        let picked: Vec<(&str, Side)> = suggestions
+            .iter()
+            .map(|s| (s.market.id.as_str(), s.side))
+            .collect();
+
+        // The $80 strike is under half of spot and "reach" pays on a rally only.
+        assert_eq!(picked.len(), 2);
+        assert!(picked.contains(&("sol-dip-150", Side::Yes)));
+        assert!(picked.contains(&("sol-above-160", Side::No)));
+        assert!(
+            suggestions
+                .windows(2)
+                .all(|pair| pair[0].cost_pct <= pair[1].cost_pct)
+        );
+        let dip = &suggestions[picked.iter().position(|p| p.0 == "sol-dip-150").unwrap()];
+        assert_eq!(dip.share_price, 0.2);
+        assert_eq!(dip.protected_range.high, 150.0);
+    }
+
+    #[test]
+    fn min_strike_ratio_bounds_the_strikes() {
+        let engine = HedgeEngine {
+            min_strike_ratio: 0.3,
+            ..HedgeEngine::new()
+        };
+        let suggestions = engine.suggest(&[sol(10.0)], &catalog());
+        assert!(suggestions.iter().any(|s| s.market.id == "sol-dip-80"));
+
+        let engine = HedgeEngine {
+            min_strike_ratio: 0.78,
+            ..HedgeEngine::new()
+        };
+        let ids: Vec<String> = engine
+            .suggest(&[sol(10.0)], &catalog())
+            .into_iter()
+            .map(|s| s.market.id)
+            .collect();
+        assert_eq!(ids, ["sol-above-160"]);
+    }
+
+    #[test]
+    fn scenarios_refuse_markets_that_do_not_pay_on_a_fall() {
+        let reach = Market::binary("sol-reach-180", "Will SOL reach $180 by December 31?", 0.9);
+        let result = HedgeScenario::build(
+            sol(10.0),
+            reach,
+            terms("Will SOL reach $180 by December 31?"),
+            1.0,
+        );
+        assert!(matches!(result, Err(AppError::HedgeSizingErr(_))));
+
+        let dip = Market::binary("sol-dip-150", "Will SOL dip to $150 by December 31?", 0.2);
+        let scenario = HedgeScenario::build(
+            sol(10.0),
+            dip,
+            terms("Will SOL dip to $150 by December 31?"),
+            1.0,
+        )
+        .unwrap();
+        assert_eq!(scenario.side, Side::Yes);
+        assert!(
+            scenario
+                .points
+                .iter()
+                .any(|point| point.terminal_price == 150.0)
+        );
+    }
+}
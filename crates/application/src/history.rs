@@ -0,0 +1,427 @@
+use crate::AppError;
+use crate::coingecko::{CoinGeckoClient, CoinIds};
+use crate::prices::{AssetKey, PriceQuote};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use utoipa::ToSchema;
+
+/// How far back the store keeps and backfills prices.
+pub const MAX_HISTORY_DAYS: u32 = 90;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PricePoint {
+    /// Unix milliseconds.
+    pub time: i64,
+    pub usd: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+pub struct Candle {
+    /// Start of the interval, unix milliseconds.
+    pub time: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+/// One line of the history file.
+#[derive(Serialize, Deserialize)]
+struct Record {
+    key: AssetKey,
+    time: i64,
+    usd: f64,
+}
+
+fn unix_ms(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+/// `15m`, `1h`, `6h`, `1d`...
+pub fn parse_interval(interval: &str) -> Option<Duration> {
+    let interval = interval.trim();
+    let (count, unit) = interval.split_at(interval.len().checked_sub(1)?);
+    let count: u64 = count.parse().ok().filter(|count| *count > 0)?;
+    let unit = match unit {
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(Duration::from_secs(count * unit))
+}
+
+/// Price snapshots per asset, kept in memory and appended to a JSON lines
+/// file so they survive restarts.
+///
+/// Snapshots closer than `min_spacing` to the previous one are skipped, the
+/// updater refreshes far more often than a chart needs.
+#[derive(Debug, Clone)]
+pub struct PriceHistory {
+    series: HashMap<AssetKey, Vec<PricePoint>>,
+    /// Days already backfilled per asset, found or not.
+    backfilled: HashMap<AssetKey, u32>,
+    file: Option<PathBuf>,
+    pub min_spacing: Duration,
+}
+
+impl Default for PriceHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PriceHistory {
+    /// Memory only.
+    pub fn new() -> Self {
+        Self {
+            series: HashMap::new(),
+            backfilled: HashMap::new(),
+            file: None,
+            min_spacing: Duration::from_secs(5 * 60),
+        }
+    }
+
+    /// Loads `path` if it exists and rewrites it without the snapshots
+    /// older than [`MAX_HISTORY_DAYS`]. Unreadable lines are dropped.
+    pub async fn open(path: impl Into<PathBuf>) -> Result<Self, AppError> {
+        let path = path.into();
+        let failed = |err: std::io::Error| AppError::PriceHistoryErr(err.to_string());
+
+        let mut history = Self::new();
+        match fs::read_to_string(&path).await {
+            Ok(contents) => {
+                for record in contents
+                    .lines()
+                    .filter_map(|line| serde_json::from_str::<Record>(line).ok())
+                {
+                    history
+                        .series
+                        .entry(record.key)
+                        .or_default()
+                        .push(PricePoint {
+                            time: record.time,
+                            usd: record.usd,
+                        });
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(failed(err)),
+        }
+
+        let oldest = unix_ms(SystemTime::now()) - MAX_HISTORY_DAYS as i64 * DAY_MS;
+        for points in history.series.values_mut() {
+            points.retain(|point| point.time >= oldest);
+            points.sort_by_key(|point| point.time);
+            points.dedup_by_key(|point| point.time);
+        }
+        history.series.retain(|_, points| !points.is_empty());
+
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).await.map_err(failed)?;
+        }
+        let mut contents = String::new();
+        for (key, points) in &history.series {
+            for point in points {
+                contents.push_str(&record_line(key, point));
+            }
+        }
+        fs::write(&path, contents).await.map_err(failed)?;
+
+        history.file = Some(path);
+        Ok(history)
+    }
+
+    /// Snapshots fresh quotes, skipping the ones taken too soon after the
+    /// previous snapshot of the same asset.
+    pub fn record(&mut self, quotes: &[PriceQuote]) -> Appended {
+        let spacing = self.min_spacing.as_millis() as i64;
+        let mut new = Vec::new();
+        for quote in quotes {
+            let point = PricePoint {
+                time: unix_ms(quote.last_updated),
+                usd: quote.usd,
+            };
+            let points = self.series.entry(quote.key.clone()).or_default();
+            if points
+                .last()
+                .is_some_and(|last| point.time - last.time < spacing)
+            {
+                continue;
+            }
+            points.push(point);
+            new.push((quote.key.clone(), point));
+        }
+        self.appended(&new)
+    }
+
+    pub fn needs_backfill(&self, key: &AssetKey, days: u32) -> bool {
+        self.backfilled.get(key).is_none_or(|done| *done < days)
+    }
+
+    /// Merges what [`HistorySource::fetch`] returned for the last `days`,
+    /// keeping the snapshots already stored.
+    pub fn backfill(&mut self, key: &AssetKey, days: u32, fetched: Vec<PricePoint>) -> Appended {
+        let points = self.series.entry(key.clone()).or_default();
+        let new: Vec<(AssetKey, PricePoint)> = fetched
+            .into_iter()
+            .filter(|point| {
+                points
+                    .binary_search_by_key(&point.time, |known| known.time)
+                    .is_err()
+            })
+            .map(|point| (key.clone(), point))
+            .collect();
+        points.extend(new.iter().map(|(_, point)| *point));
+        points.sort_by_key(|point| point.time);
+
+        let done = self.backfilled.entry(key.clone()).or_default();
+        *done = (*done).max(days);
+        self.appended(&new)
+    }
+
+    pub fn points(&self, key: &AssetKey, since: SystemTime) -> &[PricePoint] {
+        let Some(points) = self.series.get(key) else {
+            return &[];
+        };
+        let since = unix_ms(since);
+        &points[points.partition_point(|point| point.time < since)..]
+    }
+
+    /// Candles of `interval` aligned to the unix epoch, empty intervals
+    /// are left out.
+    pub fn ohlc(&self, key: &AssetKey, interval: Duration, since: SystemTime) -> Vec<Candle> {
+        let width = (interval.as_millis() as i64).max(1);
+        let mut candles: Vec<Candle> = Vec::new();
+        for point in self.points(key, since) {
+            let start = point.time - point.time.rem_euclid(width);
+            match candles.last_mut() {
+                Some(candle) if candle.time == start => {
+                    candle.high = candle.high.max(point.usd);
+                    candle.low = candle.low.min(point.usd);
+                    candle.close = point.usd;
+                }
+                _ => candles.push(Candle {
+                    time: start,
+                    open: point.usd,
+                    high: point.usd,
+                    low: point.usd,
+                    close: point.usd,
+                }),
+            }
+        }
+        candles
+    }
+
+    fn appended(&self, points: &[(AssetKey, PricePoint)]) -> Appended {
+        Appended {
+            file: self.file.clone().filter(|_| !points.is_empty()),
+            lines: points
+                .iter()
+                .map(|(key, point)| record_line(key, point))
+                .collect(),
+        }
+    }
+}
+
+/// Lines [`PriceHistory::record`] and [`PriceHistory::backfill`] added in
+/// memory, still to be written to the file. Writing them does not need the
+/// history, so a lock around it can be released first.
+#[must_use = "the snapshots are not saved until written"]
+pub struct Appended {
+    file: Option<PathBuf>,
+    lines: String,
+}
+
+impl Appended {
+    pub async fn write(self) -> Result<(), AppError> {
+        let Some(path) = self.file else {
+            return Ok(());
+        };
+        let failed = |err: std::io::Error| AppError::PriceHistoryErr(err.to_string());
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .map_err(failed)?;
+        file.write_all(self.lines.as_bytes()).await.map_err(failed)
+    }
+}
+
+fn record_line(key: &AssetKey, point: &PricePoint) -> String {
+    let record = Record {
+        key: key.clone(),
+        time: point.time,
+        usd: point.usd,
+    };
+    serde_json::to_string(&record).unwrap() + "\n"
+}
+
+/// Past prices from CoinGecko `market_chart`, symbols only.
+pub struct HistorySource {
+    client: CoinGeckoClient,
+    coin_ids: CoinIds,
+}
+
+impl HistorySource {
+    pub fn with_coins(
+        client: CoinGeckoClient,
+        coins: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Self {
+        Self {
+            client,
+            coin_ids: CoinIds::with_coins(coins),
+        }
+    }
+
+    /// Hourly prices of the last `days`, empty for assets CoinGecko does
+    /// not know.
+    pub async fn fetch(&self, key: &AssetKey, days: u32) -> Result<Vec<PricePoint>, AppError> {
+        let AssetKey::Symbol { symbol } = key else {
+            return Ok(Vec::new());
+        };
+        let Some(id) = self.coin_ids.resolve(&self.client, symbol).await? else {
+            return Ok(Vec::new());
+        };
+
+        let days = days.clamp(2, MAX_HISTORY_DAYS);
+        Ok(self
+            .client
+            .market_chart(&id, days)
+            .await?
+            .into_iter()
+            .map(|(time, usd)| PricePoint { time, usd })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(symbol: &str, secs: u64, usd: f64) -> PriceQuote {
+        PriceQuote {
+            key: AssetKey::symbol(symbol),
+            usd,
+            sources: Vec::new(),
+            last_updated: UNIX_EPOCH + Duration::from_secs(secs),
+        }
+    }
+
+    #[test]
+    fn parses_intervals() {
+        assert_eq!(parse_interval("15m"), Some(Duration::from_secs(900)));
+        assert_eq!(parse_interval("6h"), Some(Duration::from_secs(6 * 3600)));
+        assert_eq!(parse_interval("1d"), Some(Duration::from_secs(86400)));
+        assert_eq!(parse_interval("0h"), None);
+        assert_eq!(parse_interval("h"), None);
+        assert_eq!(parse_interval("1w"), None);
+    }
+
+    #[tokio::test]
+    async fn builds_candles() {
+        let mut history = PriceHistory::new();
+        history.min_spacing = Duration::ZERO;
+        history
+            .record(&[
+                quote("SOL", 3600, 100.0),
+                quote("SOL", 3600 + 600, 104.0),
+                quote("SOL", 3600 + 1200, 98.0),
+                quote("SOL", 3600 + 1800, 101.0),
+                quote("SOL", 7200 + 60, 102.0),
+            ])
+            .write()
+            .await
+            .unwrap();
+
+        let candles = history.ohlc(
+            &AssetKey::symbol("SOL"),
+            Duration::from_secs(3600),
+            UNIX_EPOCH,
+        );
+        assert_eq!(
+            candles,
+            [
+                Candle {
+                    time: 3_600_000,
+                    open: 100.0,
+                    high: 104.0,
+                    low: 98.0,
+                    close: 101.0,
+                },
+                Candle {
+                    time: 7_200_000,
+                    open: 102.0,
+                    high: 102.0,
+                    low: 102.0,
+                    close: 102.0,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn skips_snapshots_closer_than_min_spacing() {
+        let mut history = PriceHistory::new();
+        history
+            .record(&[quote("SOL", 0, 100.0), quote("SOL", 30, 101.0)])
+            .write()
+            .await
+            .unwrap();
+        history
+            .record(&[quote("SOL", 301, 102.0)])
+            .write()
+            .await
+            .unwrap();
+
+        let prices: Vec<f64> = history
+            .points(&AssetKey::symbol("SOL"), UNIX_EPOCH)
+            .iter()
+            .map(|point| point.usd)
+            .collect();
+        assert_eq!(prices, [100.0, 102.0]);
+    }
+
+    #[tokio::test]
+    async fn survives_restarts() {
+        let path = std::env::temp_dir().join(format!("price-history-{}.jsonl", std::process::id()));
+        let now = unix_ms(SystemTime::now());
+        let sol = AssetKey::symbol("SOL");
+
+        let mut history = PriceHistory::open(&path).await.unwrap();
+        history
+            .backfill(
+                &sol,
+                7,
+                vec![
+                    PricePoint {
+                        time: now - DAY_MS,
+                        usd: 150.0,
+                    },
+                    PricePoint {
+                        time: now - 2 * MAX_HISTORY_DAYS as i64 * DAY_MS,
+                        usd: 20.0,
+                    },
+                ],
+            )
+            .write()
+            .await
+            .unwrap();
+        assert!(!history.needs_backfill(&sol, 7));
+        assert!(history.needs_backfill(&sol, 30));
+
+        let reopened = PriceHistory::open(&path).await.unwrap();
+        fs::remove_file(&path).await.unwrap();
+        assert_eq!(reopened.points(&sol, UNIX_EPOCH).len(), 1);
+        assert_eq!(reopened.points(&sol, UNIX_EPOCH)[0].usd, 150.0);
+    }
+}
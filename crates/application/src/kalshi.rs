@@ -0,0 +1,419 @@
+use crate::AppError;
+use crate::market::{Market, MarketStatus, Venue};
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+pub const KALSHI_API_URL: &str = "https://api.elections.kalshi.com/trade-api/v2";
+
+/// Kalshi quotes prices in cents, every conversion below divides by this.
+const CENTS_PER_DOLLAR: f64 = 100.0;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct KalshiMarket {
+    pub ticker: String,
+    #[serde(default)]
+    pub event_ticker: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub subtitle: String,
+    #[serde(default)]
+    pub rules_primary: String,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub yes_bid: Option<u32>,
+    #[serde(default)]
+    pub yes_ask: Option<u32>,
+    #[serde(default)]
+    pub no_bid: Option<u32>,
+    #[serde(default)]
+    pub no_ask: Option<u32>,
+    #[serde(default)]
+    pub last_price: Option<u32>,
+    #[serde(default)]
+    pub volume: Option<u64>,
+    #[serde(default)]
+    pub liquidity: Option<u64>,
+    #[serde(default)]
+    pub close_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct KalshiEvent {
+    pub event_ticker: String,
+    #[serde(default)]
+    pub series_ticker: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub sub_title: String,
+    #[serde(default)]
+    pub category: String,
+    #[serde(default)]
+    pub markets: Vec<KalshiMarket>,
+}
+
+/// One page of a cursor paginated listing. An empty `cursor` means the last page.
+#[derive(Debug, Clone)]
+pub struct KalshiPage<T> {
+    pub items: Vec<T>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EventsResponse {
+    #[serde(default)]
+    events: Vec<KalshiEvent>,
+    #[serde(default)]
+    cursor: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MarketsResponse {
+    #[serde(default)]
+    markets: Vec<KalshiMarket>,
+    #[serde(default)]
+    cursor: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MarketResponse {
+    market: KalshiMarket,
+}
+
+#[derive(Debug, Deserialize)]
+struct OrderbookResponse {
+    orderbook: KalshiOrderbook,
+}
+
+/// Resting bids as `[price_cents, quantity]` levels, sorted by ascending price.
+///
+/// Kalshi only publishes bids: a YES ask is the complement of the best NO bid.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct KalshiOrderbook {
+    #[serde(default, deserialize_with = "levels")]
+    pub yes: Vec<(u32, u64)>,
+    #[serde(default, deserialize_with = "levels")]
+    pub no: Vec<(u32, u64)>,
+}
+
+fn levels<'de, D>(deserializer: D) -> Result<Vec<(u32, u64)>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Option::<Vec<(u32, u64)>>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Top of book in dollars per contract.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct KalshiQuote {
+    pub yes_bid: Option<f64>,
+    pub yes_ask: Option<f64>,
+    pub no_bid: Option<f64>,
+    pub no_ask: Option<f64>,
+}
+
+impl KalshiOrderbook {
+    pub fn quote(&self) -> KalshiQuote {
+        let best = |levels: &[(u32, u64)]| levels.iter().map(|&(price, _)| price).max();
+        let yes_bid = best(&self.yes);
+        let no_bid = best(&self.no);
+        KalshiQuote {
+            yes_bid: yes_bid.map(cents),
+            yes_ask: no_bid.map(|p| cents(100 - p.min(100))),
+            no_bid: no_bid.map(cents),
+            no_ask: yes_bid.map(|p| cents(100 - p.min(100))),
+        }
+    }
+}
+
+fn cents(price: u32) -> f64 {
+    price as f64 / CENTS_PER_DOLLAR
+}
+
+impl KalshiMarket {
+    pub fn quote(&self) -> KalshiQuote {
+        KalshiQuote {
+            yes_bid: self.yes_bid.map(cents),
+            yes_ask: self.yes_ask.map(cents),
+            no_bid: self.no_bid.map(cents),
+            no_ask: self.no_ask.map(cents),
+        }
+    }
+
+    /// YES price used for the normalized market: the bid/ask midpoint when both
+    /// sides are quoted, otherwise the last traded price. Kalshi sends an empty
+    /// book as a 0 bid and a 100 ask, and a market never traded with a 0 last
+    /// price.
+    pub fn yes_price(&self) -> Option<f64> {
+        match (self.yes_bid, self.yes_ask) {
+            (Some(bid), Some(ask)) if bid > 0 && ask < 100 => Some(cents(bid + ask) / 2.0),
+            _ => self.last_price.filter(|&price| price > 0).map(cents),
+        }
+    }
+}
+
+impl From<KalshiMarket> for Market {
+    fn from(market: KalshiMarket) -> Self {
+        // No quote means no price, not a free contract.
+        let outcome_prices = market
+            .yes_price()
+            .map_or_else(Vec::new, |yes| vec![yes, 1.0 - yes]);
+        let status = match market.status.as_str() {
+            "active" | "open" => MarketStatus::Active,
+            "settled" | "finalized" => MarketStatus::Archived,
+            _ => MarketStatus::Closed,
+        };
+        let question = if market.subtitle.is_empty() {
+            market.title
+        } else {
+            format!("{} {}", market.title, market.subtitle)
+        };
+
+        Self {
+            venue: Venue::Kalshi,
+            id: market.ticker,
+            question,
+            rules: market.rules_primary,
+            outcomes: vec!["Yes".to_string(), "No".to_string()],
+            outcome_prices,
+            token_ids: Vec::new(),
+            volume: market.volume.unwrap_or(0) as f64,
+            liquidity: market.liquidity.unwrap_or(0) as f64 / CENTS_PER_DOLLAR,
+            end_date: market.close_time,
+            status,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct KalshiMarketsQuery<'a> {
+    pub event_ticker: Option<&'a str>,
+    pub series_ticker: Option<&'a str>,
+    pub status: Option<&'a str>,
+    pub cursor: Option<&'a str>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Clone)]
+pub struct KalshiClient {
+    client: Client,
+    base_url: String,
+}
+
+impl Default for KalshiClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KalshiClient {
+    pub fn new() -> Self {
+        Self::with_base_url(KALSHI_API_URL)
+    }
+
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub async fn list_events(
+        &self,
+        status: Option<&str>,
+        cursor: Option<&str>,
+    ) -> Result<KalshiPage<KalshiEvent>, AppError> {
+        let mut query = vec![("with_nested_markets", "true".to_string())];
+        if let Some(status) = status {
+            query.push(("status", status.to_string()));
+        }
+        if let Some(cursor) = cursor {
+            query.push(("cursor", cursor.to_string()));
+        }
+
+        let response: EventsResponse = self.get_json("/events", &query).await?;
+        Ok(KalshiPage {
+            items: response.events,
+            cursor: Some(response.cursor).filter(|c| !c.is_empty()),
+        })
+    }
+
+    pub async fn list_markets(
+        &self,
+        params: &KalshiMarketsQuery<'_>,
+    ) -> Result<KalshiPage<KalshiMarket>, AppError> {
+        let mut query = Vec::new();
+        if let Some(event_ticker) = params.event_ticker {
+            query.push(("event_ticker", event_ticker.to_string()));
+        }
+        if let Some(series_ticker) = params.series_ticker {
+            query.push(("series_ticker", series_ticker.to_string()));
+        }
+        if let Some(status) = params.status {
+            query.push(("status", status.to_string()));
+        }
+        if let Some(cursor) = params.cursor {
+            query.push(("cursor", cursor.to_string()));
+        }
+        if let Some(limit) = params.limit {
+            query.push(("limit", limit.to_string()));
+        }
+
+        let response: MarketsResponse = self.get_json("/markets", &query).await?;
+        Ok(KalshiPage {
+            items: response.markets,
+            cursor: Some(response.cursor).filter(|c| !c.is_empty()),
+        })
+    }
+
+    pub async fn get_market(&self, ticker: &str) -> Result<KalshiMarket, AppError> {
+        let response: MarketResponse = self.get_json(&format!("/markets/{}", ticker), &[]).await?;
+        Ok(response.market)
+    }
+
+    pub async fn get_orderbook(&self, ticker: &str) -> Result<KalshiOrderbook, AppError> {
+        let response: OrderbookResponse = self
+            .get_json(&format!("/markets/{}/orderbook", ticker), &[])
+            .await?;
+        Ok(response.orderbook)
+    }
+
+    pub async fn get_quote(&self, ticker: &str) -> Result<KalshiQuote, AppError> {
+        Ok(self.get_orderbook(ticker).await?.quote())
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<T, AppError> {
+        let response = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .query(query)
+            .header("accept", "application/json")
+            .send()
+            .await
+            .map_err(|_| AppError::KalshiApiErr)?;
+
+        if !response.status().is_success() {
+            return Err(AppError::KalshiApiErr);
+        }
+
+        response
+            .json::<T>()
+            .await
+            .map_err(|_| AppError::KalshiApiErr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stand_in;
+
+    #[tokio::test]
+    async fn lists_events_with_nested_markets() {
+        let (url, server) = stand_in::serve_json(vec![
+            r#"{"events": [{"event_ticker": "KXBTCMAXY-25", "series_ticker": "KXBTCMAXY", "title": "How high will Bitcoin get this year?", "category": "Crypto", "markets": [{"ticker": "KXBTCMAXY-25-DEC31-149999.99", "title": "Will Bitcoin be above $150,000?", "status": "active", "yes_bid": 12, "yes_ask": 14}]}], "cursor": "next-page"}"#.to_string(),
+            r#"{"events": [], "cursor": ""}"#.to_string(),
+        ])
+        .await;
+        let client = KalshiClient::with_base_url(format!("{url}/"));
+
+        let page = client.list_events(Some("open"), None).await.unwrap();
+        assert_eq!(page.cursor.as_deref(), Some("next-page"));
+        assert_eq!(page.items[0].category, "Crypto");
+        assert_eq!(page.items[0].markets[0].yes_price(), Some(0.13));
+
+        let last = client
+            .list_events(None, page.cursor.as_deref())
+            .await
+            .unwrap();
+        assert!(last.items.is_empty());
+        assert_eq!(last.cursor, None);
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("GET /events?with_nested_markets=true&status=open "));
+        assert!(requests[1].contains("cursor=next-page"));
+    }
+
+    #[tokio::test]
+    async fn normalizes_a_market() {
+        let (url, server) = stand_in::serve_json(vec![
+            r#"{"market": {"ticker": "KXSOL-25-T200", "event_ticker": "KXSOL-25", "title": "Will SOL be above $200", "subtitle": "on Dec 31?", "status": "active", "yes_bid": 40, "yes_ask": 44, "no_bid": 56, "no_ask": 60, "last_price": 41, "volume": 1500, "liquidity": 250000, "close_time": "2025-12-31T22:00:00Z"}}"#.to_string(),
+            r#"{"market": {"ticker": "KXSOL-25-T900", "status": "active"}}"#.to_string(),
+        ])
+        .await;
+        let client = KalshiClient::with_base_url(url);
+
+        let market: Market = client.get_market("KXSOL-25-T200").await.unwrap().into();
+        assert_eq!(market.venue, Venue::Kalshi);
+        assert_eq!(market.question, "Will SOL be above $200 on Dec 31?");
+        assert_eq!(market.yes_price(), Some(0.42));
+        assert!((market.no_price().unwrap() - 0.58).abs() < 1e-9);
+        assert_eq!(market.liquidity, 2500.0);
+        assert_eq!(market.status, MarketStatus::Active);
+
+        let unquoted: Market = client.get_market("KXSOL-25-T900").await.unwrap().into();
+        assert!(unquoted.outcome_prices.is_empty());
+        assert_eq!(unquoted.yes_price(), None);
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("GET /markets/KXSOL-25-T200 "));
+    }
+
+    #[test]
+    fn empty_books_have_no_mid() {
+        let market = |json: &str| serde_json::from_str::<KalshiMarket>(json).unwrap();
+        let base = r#""ticker": "KXSOL-25-T900", "status": "active""#;
+
+        let empty = market(&format!(
+            r#"{{{base}, "yes_bid": 0, "yes_ask": 100, "last_price": 0}}"#
+        ));
+        assert_eq!(empty.yes_price(), None);
+        let traded = market(&format!(
+            r#"{{{base}, "yes_bid": 0, "yes_ask": 100, "last_price": 7}}"#
+        ));
+        assert_eq!(traded.yes_price(), Some(0.07));
+        let one_sided = market(&format!(r#"{{{base}, "yes_bid": 0, "yes_ask": 20}}"#));
+        assert_eq!(one_sided.yes_price(), None);
+    }
+
+    #[tokio::test]
+    async fn quotes_from_the_orderbook_in_dollars() {
+        let (url, server) = stand_in::serve_json(vec![
+            r#"{"orderbook": {"yes": [[30, 100], [35, 20]], "no": [[60, 50], [62, 10]]}}"#
+                .to_string(),
+            r#"{"orderbook": {"yes": null, "no": [[99, 1]]}}"#.to_string(),
+        ])
+        .await;
+        let client = KalshiClient::with_base_url(url);
+
+        let quote = client.get_quote("KXSOL-25-T200").await.unwrap();
+        assert_eq!(quote.yes_bid, Some(0.35));
+        assert_eq!(quote.yes_ask, Some(0.38));
+        assert_eq!(quote.no_bid, Some(0.62));
+        assert_eq!(quote.no_ask, Some(0.65));
+
+        let one_sided = client.get_orderbook("KXSOL-25-T900").await.unwrap();
+        assert!(one_sided.yes.is_empty());
+        let quote = one_sided.quote();
+        assert_eq!(quote.yes_bid, None);
+        assert_eq!(quote.yes_ask, Some(0.01));
+        assert_eq!(quote.no_ask, None);
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("GET /markets/KXSOL-25-T200/orderbook "));
+    }
+
+    #[test]
+    fn converts_cents_to_dollars() {
+        assert_eq!(cents(0), 0.0);
+        assert_eq!(cents(37), 0.37);
+        assert_eq!(cents(100), 1.0);
+    }
+}
@@ -1,394 +1,407 @@
-use axum::http::StatusCode;
-use reqwest::Client;
-use serde::Deserialize;
-use serde::Serialize;
-use solana_client::rpc_client::RpcClient;
-use solana_sdk::pubkey::Pubkey;
-use std::collections::HashMap;
-use std::str::FromStr;
-
-pub struct AssetsRow {
-    pub asset: String,
-    pub balance: String,
-    pub value: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct ZerionPortfolioResponse {
-    links: ZerionLinks,
-    data: ZerionPortfolioData,
-}
-
-#[derive(Debug, Deserialize)]
-struct ZerionLinks {
-    #[serde(rename = "self")]
-    self_link: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct ZerionPortfolioData {
-    #[serde(rename = "type")]
-    data_type: String,
-    id: String,
-    attributes: ZerionAttributes,
-}
-
-#[derive(Debug, Deserialize)]
-struct ZerionAttributes {
-    positions_distribution_by_type: HashMap<String, f64>,
-    positions_distribution_by_chain: HashMap<String, f64>,
-    total: ZerionTotal,
-    changes: ZerionChanges,
-}
-
-#[derive(Debug, Deserialize)]
-struct ZerionTotal {
-    positions: f64,
-}
-
-#[derive(Debug, Deserialize)]
-struct ZerionChanges {
-    absolute_1d: f64,
-    percent_1d: Option<f64>,
-}
-
-struct ZerionClient {
-    client: Client,
-    auth_header: String,
-}
-
-impl ZerionClient {
-    pub fn new() -> Self {
-        Self {
-            client: Client::new(),
-            auth_header: "Basic emtfZGV2X2RhNzg4OWRmMjUwZTQ1ZTFhNzAwY2M3OTg1YjE2MTQ3Og=="
-                .to_string(),
-        }
-    }
-
-    pub async fn get_portfolio(&self, address: &str) -> Result<ZerionPortfolioResponse, AppError> {
-        let url = format!(
-            "https://api.zerion.io/v1/wallets/{}/portfolio?currency=usd",
-            address
-        );
-
-        let response = self
-            .client
-            .get(&url)
-            .header("accept", "application/json")
-            .header("authorization", &self.auth_header)
-            .send()
-            .await
-            .map_err(|_| AppError::ZerionApiErr)?;
-
-        if !response.status().is_success() {
-            return Err(AppError::ZerionApiErr);
-        }
-
-        let portfolio_data = response
-            .json::<ZerionPortfolioResponse>()
-            .await
-            .map_err(|_| AppError::ZerionApiErr)?;
-
-        Ok(portfolio_data)
-    }
-}
-
-#[derive(Debug)]
-pub enum AppError {
-    InvalidWalletAddress(String),
-    ErrorFetchingBalance,
-    ExchangePriceApiErr,
-    PolymarketApiErr,
-    ZerionApiErr,
-    SolanaRpcErr,
-}
-
-#[derive(Debug)]
-pub struct TradeCalculation {
-    pub estimated_cost: f64,
-    pub price_per_share: f64,
-    pub shares: usize,
-    pub total_cost: f64,
-}
-
-impl std::fmt::Display for AppError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            AppError::InvalidWalletAddress(e) => write!(f, "Invalid Wallet Address: {}", e),
-            AppError::ErrorFetchingBalance => write!(f, "Error fetching balance"),
-            AppError::ExchangePriceApiErr => todo!(),
-            AppError::PolymarketApiErr => todo!(),
-            AppError::ZerionApiErr => todo!(),
-            AppError::SolanaRpcErr => todo!(),
-        }
-    }
-}
-
-impl Into<StatusCode> for AppError {
-    fn into(self) -> StatusCode {
-        match self {
-            AppError::PolymarketApiErr => StatusCode::INTERNAL_SERVER_ERROR,
-            AppError::ZerionApiErr => StatusCode::BAD_REQUEST,
-            AppError::SolanaRpcErr => StatusCode::BAD_REQUEST,
-            AppError::InvalidWalletAddress(_) => StatusCode::BAD_REQUEST,
-            AppError::ErrorFetchingBalance => StatusCode::INTERNAL_SERVER_ERROR,
-            AppError::ExchangePriceApiErr => StatusCode::INTERNAL_SERVER_ERROR,
-        }
-    }
-}
-
-pub struct WalletService {
-    zerion_client: ZerionClient,
-    // Keep solana client if you still need it for specific Solana-only features
-}
-
-impl WalletService {
-    pub fn new() -> Self {
-        Self {
-            zerion_client: ZerionClient::new(),
-        }
-    }
-
-    pub async fn get_wallet_assets(&self, address: &str) -> Result<Vec<AssetsRow>, AppError> {
-        // First try Zerion API for multi-chain support
-        match self.zerion_client.get_portfolio(address).await {
-            Ok(portfolio) => self.zerion_portfolio_to_assets(portfolio, address).await,
-            Err(_) => {
-                // If Zerion fails, fall back to Solana-only approach
-                self.get_solana_assets(address).await
-            }
-        }
-    }
-
-    async fn zerion_portfolio_to_assets(
-        &self,
-        portfolio: ZerionPortfolioResponse,
-        address: &str,
-    ) -> Result<Vec<AssetsRow>, AppError> {
-        let mut assets = Vec::new();
-
-        // Check if portfolio is empty (likely invalid address or no assets)
-        if portfolio.data.attributes.total.positions == 0.0 {
-            // Try to get detailed positions to see if there are actually assets
-            // or if this is an invalid address
-            return self.get_detailed_positions(address).await;
-        }
-
-        // Process chain distribution
-        for (chain, value) in portfolio.data.attributes.positions_distribution_by_chain {
-            if value > 0.0 {
-                assets.push(AssetsRow {
-                    asset: chain,
-                    balance: format!("{:.2}", value), // This is USD value
-                    value: format!("{:.2}", value),
-                });
-            }
-        }
-
-        // If we have assets, return them
-        if !assets.is_empty() {
-            Ok(assets)
-        } else {
-            // Fall back to Solana if no assets found
-            self.get_solana_assets(address).await
-        }
-    }
-
-    async fn get_detailed_positions(&self, address: &str) -> Result<Vec<AssetsRow>, AppError> {
-        // You can implement this later to get individual token positions
-        // For now, fall back to Solana
-        self.get_solana_assets(address).await
-    }
-
-    async fn get_solana_assets(&self, address: &str) -> Result<Vec<AssetsRow>, AppError> {
-        // Your existing Solana balance logic here
-        let lamport_balance = LamportBalance::get(address.to_string())
-            .await
-            .map_err(|_| AppError::SolanaRpcErr)?;
-
-        let sol = lamport_balance.to_sol();
-        // For USD conversion, you might need to get current SOL price
-        let usd = sol * 100.0; // Placeholder - replace with actual rate
-
-        let assets = vec![AssetsRow {
-            asset: "SOL".to_string(),
-            balance: format!("{:.6}", sol),
-            value: format!("{:.2}", usd),
-        }];
-
-        Ok(assets)
-    }
-}
-
-impl std::error::Error for AppError {}
-
-pub struct LamportBalance(u64);
-
-#[derive(Debug, Clone, Serialize)]
-pub struct ExchangePrices {
-    pub last_updated: std::time::SystemTime,
-    pub sol_to_usd: f64,
-    pub btc_to_usd: f64,
-    pub eth_to_usd: f64,
-}
-
-#[derive(Debug, Clone, Serialize)]
-pub struct PolymarketSolana260 {
-    pub last_updated: std::time::SystemTime,
-    pub answer_no_multiplier: f64,
-}
-
-impl PolymarketSolana260 {
-    pub fn new() -> Self {
-        Self {
-            last_updated: std::time::SystemTime::UNIX_EPOCH,
-            answer_no_multiplier: 0.0,
-        }
-    }
-
-    pub async fn update() -> Result<f64, AppError> {
-        let url = "https://gamma-api.polymarket.com/markets/slug/will-solana-reach-260-before-2026-327-264-879-598";
-        let response = reqwest::get(url)
-            .await
-            .map_err(|_| AppError::PolymarketApiErr)?;
-        let json: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|_| AppError::PolymarketApiErr)?;
-
-        let outcome_prices = json["outcomePrices"]
-            .as_str()
-            .ok_or(AppError::PolymarketApiErr)?;
-
-        let prices_value: serde_json::Value =
-            serde_json::from_str(outcome_prices).map_err(|_| AppError::PolymarketApiErr)?;
-
-        let raw_prices: Vec<f64> = prices_value
-            .as_array()
-            .ok_or(AppError::PolymarketApiErr)?
-            .iter()
-            .map(|v| v.as_str().and_then(|s| s.parse().ok()).unwrap_or(0.0))
-            .collect();
-
-        const POLYMARKET_FEE: f64 = 0.02;
-
-        let website_prices: Vec<f64> = raw_prices
-            .iter()
-            .map(|&raw_price| raw_price + POLYMARKET_FEE)
-            .collect();
-        Ok(website_prices[1])
-    }
-}
-
-impl ExchangePrices {
-    pub fn new() -> Self {
-        Self {
-            last_updated: std::time::SystemTime::UNIX_EPOCH,
-            sol_to_usd: 0.0,
-            btc_to_usd: 0.0,
-            eth_to_usd: 0.0,
-        }
-    }
-    pub async fn update(&mut self) -> Result<(), AppError> {
-        let sol_future = Self::get_sol_price();
-        let btc_future = Self::get_btc_price();
-        let eth_future = Self::get_eth_price();
-
-        let (sol_price, btc_price, eth_price) =
-            tokio::try_join!(sol_future, btc_future, eth_future)?;
-
-        self.sol_to_usd = sol_price;
-        self.btc_to_usd = btc_price;
-        self.eth_to_usd = eth_price;
-        self.last_updated = std::time::SystemTime::now();
-
-        Ok(())
-    }
-
-    pub async fn get_sol_price() -> Result<f64, AppError> {
-        let url = "https://api.coingecko.com/api/v3/simple/price?ids=solana&vs_currencies=usd";
-        let response = reqwest::get(url)
-            .await
-            .map_err(|_| AppError::ExchangePriceApiErr)?;
-
-        let json: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|_| AppError::ExchangePriceApiErr)?;
-
-        json["solana"]["usd"]
-            .as_f64()
-            .ok_or(AppError::ExchangePriceApiErr)
-    }
-
-    pub async fn get_btc_price() -> Result<f64, AppError> {
-        let url = "https://api.coingecko.com/api/v3/simple/price?ids=bitcoin&vs_currencies=usd";
-        let response = reqwest::get(url)
-            .await
-            .map_err(|_| AppError::ExchangePriceApiErr)?;
-
-        let json: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|_| AppError::ExchangePriceApiErr)?;
-
-        json["bitcoin"]["usd"]
-            .as_f64()
-            .ok_or(AppError::ExchangePriceApiErr)
-    }
-
-    pub async fn get_eth_price() -> Result<f64, AppError> {
-        let url = "https://api.coingecko.com/api/v3/simple/price?ids=ethereum&vs_currencies=usd";
-        let response = reqwest::get(url)
-            .await
-            .map_err(|_| AppError::ExchangePriceApiErr)?;
-
-        let json: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|_| AppError::ExchangePriceApiErr)?;
-
-        json["ethereum"]["usd"]
-            .as_f64()
-            .ok_or(AppError::ExchangePriceApiErr)
-    }
-
-    pub fn get_price(&self, symbol: &str) -> Option<f64> {
-        match symbol.to_lowercase().as_str() {
-            "sol" | "solana" => Some(self.sol_to_usd),
-            "btc" | "bitcoin" => Some(self.btc_to_usd),
-            "eth" | "ethereum" => Some(self.eth_to_usd),
-            _ => None,
-        }
-    }
-
-    pub fn get_sol_to_usd(&self) -> f64 {
-        self.sol_to_usd
-    }
-
-    pub fn get_last_updated(&self) -> std::time::SystemTime {
-        self.last_updated
-    }
-}
-
-impl LamportBalance {
-    pub fn to_usd(&self, sol_to_usd: f64) -> f64 {
-        let self_sol = self.to_sol();
-        self_sol * sol_to_usd
-    }
-    pub fn to_sol(&self) -> f64 {
-        self.0 as f64 / 1_000_000_000.0
-    }
-    pub async fn get(wallet_address: String) -> Result<Self, AppError> {
-        let pubkey = Pubkey::from_str(&wallet_address)
-            .map_err(|_| AppError::InvalidWalletAddress(wallet_address))?;
-
-        let rpc_url = "https://api.devnet.solana.com".to_string();
-        let client = RpcClient::new(rpc_url);
-        let balance = client
-            .get_balance(&pubkey)
-            .map_err(|_| AppError::ErrorFetchingBalance)?;
-        Ok(LamportBalance(balance))
-    }
-}
+use axum::http::StatusCode;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+pub mod catalog;
+pub mod clob;
+pub mod clob_feed;
+pub mod coingecko;
+pub mod correlation;
+pub mod forecast;
+pub mod hedge;
+pub mod history;
+pub mod kalshi;
+pub mod market;
+pub mod oracle;
+pub mod polymarket;
+pub mod prices;
+pub mod pyth;
+pub mod sizing;
+pub mod solana;
+pub mod spl;
+#[cfg(test)]
+mod stand_in;
+pub mod terms;
+pub mod token_metadata;
+pub mod volatility;
+pub mod zerion;
+
+pub use catalog::MarketCatalog;
+pub use clob::{ClobClient, FillTarget};
+pub use clob_feed::{ClobFeed, QuoteUpdate, TokenQuote};
+pub use coingecko::CoinGeckoClient;
+pub use correlation::{CorrelationTracker, MarketCorrelation};
+pub use forecast::{Forecast, Forecaster};
+pub use hedge::{HedgeEngine, HedgeScenario, HedgeSuggestion, Holding};
+pub use history::{HistorySource, PriceHistory};
+pub use kalshi::KalshiClient;
+pub use market::{Asset, Market, MarketStatus, Venue};
+pub use oracle::{PriceOracle, PriceSource};
+pub use polymarket::{PolymarketClient, PolymarketMarket};
+pub use prices::{AssetKey, PriceQuote, PriceRegistry};
+pub use solana::{Cluster, SolanaConfig, SolanaRpc};
+pub use terms::MarketTerms;
+pub use token_metadata::{TokenMetadata, TokenMetadataResolver};
+pub use volatility::{Edge, VolatilityModel};
+pub use zerion::{PositionType, ZerionClient, ZerionPosition};
+
+/// Serializes API timestamps as RFC 3339 rather than serde's
+/// `{secs_since_epoch, nanos_since_epoch}`.
+pub fn rfc3339<S: serde::Serializer>(
+    time: &std::time::SystemTime,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let time = chrono::DateTime::<chrono::Utc>::from(*time);
+    serializer.collect_str(&time.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AssetsRow {
+    pub asset: String,
+    pub balance: String,
+    pub value: String,
+    /// What the row is priced by when `asset` is not a ticker, an SPL mint.
+    #[serde(skip)]
+    pub key: Option<AssetKey>,
+    /// Solana mint, `None` for native SOL and tokens on other chains.
+    pub mint: Option<String>,
+    pub name: Option<String>,
+    pub decimals: Option<u8>,
+    pub logo_uri: Option<String>,
+    /// Zerion chain id, `solana` for rows read from the chain directly.
+    pub chain: Option<String>,
+    pub position_type: Option<PositionType>,
+    /// USD price Zerion reported with the position.
+    pub price: Option<f64>,
+}
+
+impl AssetsRow {
+    pub fn price_key(&self) -> AssetKey {
+        self.key
+            .clone()
+            .unwrap_or_else(|| AssetKey::symbol(&self.asset))
+    }
+
+    /// USD price of one unit, by key when the row has one, else the price
+    /// the row came with.
+    pub fn usd_price(&self, prices: &PriceRegistry) -> Option<f64> {
+        match &self.key {
+            Some(key) => prices.get(key).map(|quote| quote.usd),
+            None => prices.get_price(&self.asset),
+        }
+        .or(self.price)
+    }
+}
+
+#[derive(Debug)]
+pub enum AppError {
+    InvalidWalletAddress(String),
+    ErrorFetchingBalance,
+    ExchangePriceApiErr,
+    PolymarketApiErr,
+    KalshiApiErr,
+    MarketTermsErr(String),
+    HedgeSizingErr(String),
+    ZerionApiErr,
+    SolanaRpcErr,
+    InvalidSolanaCluster(String),
+    PythAccountErr(String),
+    PriceHistoryErr(String),
+    StalePrice {
+        symbol: String,
+        age: std::time::Duration,
+    },
+    /// Confidence interval as a fraction of the price.
+    PriceConfidenceTooWide {
+        symbol: String,
+        confidence: f64,
+    },
+}
+
+/// Result of walking an order book, see [`clob::Orderbook::fill`].
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeCalculation {
+    /// What the filled shares would cost at the midpoint.
+    pub estimated_cost: f64,
+    /// Average fill price, fee excluded.
+    pub price_per_share: f64,
+    pub shares: f64,
+    /// What the fills actually cost, fee included.
+    pub total_cost: f64,
+    pub midpoint: f64,
+    /// `price_per_share` minus `midpoint`.
+    pub slippage: f64,
+    pub unfilled_usd: f64,
+    pub unfilled_shares: f64,
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::InvalidWalletAddress(e) => write!(f, "Invalid Wallet Address: {}", e),
+            AppError::ErrorFetchingBalance => write!(f, "Error fetching balance"),
+            AppError::ExchangePriceApiErr => write!(f, "Error fetching exchange prices"),
+            AppError::PolymarketApiErr => write!(f, "Error fetching Polymarket market data"),
+            AppError::KalshiApiErr => write!(f, "Error fetching Kalshi market data"),
+            AppError::MarketTermsErr(e) => write!(f, "Cannot parse market terms: {}", e),
+            AppError::HedgeSizingErr(e) => write!(f, "Invalid hedge size: {}", e),
+            AppError::ZerionApiErr => write!(f, "Error fetching Zerion positions"),
+            AppError::SolanaRpcErr => write!(f, "Error calling Solana RPC"),
+            AppError::InvalidSolanaCluster(e) => write!(f, "Invalid Solana cluster: {}", e),
+            AppError::PythAccountErr(e) => write!(f, "Cannot read Pyth price account: {}", e),
+            AppError::PriceHistoryErr(e) => write!(f, "Price history store failed: {}", e),
+            AppError::StalePrice { symbol, age } => {
+                write!(f, "{} price is {}s old", symbol, age.as_secs())
+            }
+            AppError::PriceConfidenceTooWide { symbol, confidence } => write!(
+                f,
+                "{} price is uncertain: confidence is {:.2}% of the price",
+                symbol,
+                confidence * 100.0
+            ),
+        }
+    }
+}
+
+impl AppError {
+    /// Stable machine readable name of the error, used in API error bodies.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::InvalidWalletAddress(_) => "invalid_wallet_address",
+            AppError::ErrorFetchingBalance => "error_fetching_balance",
+            AppError::ExchangePriceApiErr => "exchange_price_api_error",
+            AppError::PolymarketApiErr => "polymarket_api_error",
+            AppError::KalshiApiErr => "kalshi_api_error",
+            AppError::MarketTermsErr(_) => "market_terms_error",
+            AppError::HedgeSizingErr(_) => "hedge_sizing_error",
+            AppError::ZerionApiErr => "zerion_api_error",
+            AppError::SolanaRpcErr => "solana_rpc_error",
+            AppError::InvalidSolanaCluster(_) => "invalid_solana_cluster",
+            AppError::PythAccountErr(_) => "pyth_account_error",
+            AppError::PriceHistoryErr(_) => "price_history_error",
+            AppError::StalePrice { .. } => "stale_price",
+            AppError::PriceConfidenceTooWide { .. } => "price_confidence_too_wide",
+        }
+    }
+}
+
+impl From<AppError> for StatusCode {
+    fn from(err: AppError) -> Self {
+        match err {
+            AppError::PolymarketApiErr => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::KalshiApiErr => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::MarketTermsErr(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::HedgeSizingErr(_) => StatusCode::BAD_REQUEST,
+            AppError::ZerionApiErr => StatusCode::BAD_REQUEST,
+            AppError::SolanaRpcErr => StatusCode::BAD_REQUEST,
+            AppError::InvalidSolanaCluster(_) => StatusCode::BAD_REQUEST,
+            AppError::InvalidWalletAddress(_) => StatusCode::BAD_REQUEST,
+            AppError::ErrorFetchingBalance => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::ExchangePriceApiErr => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::PythAccountErr(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::PriceHistoryErr(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::StalePrice { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::PriceConfidenceTooWide { .. } => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+pub struct WalletService {
+    zerion_client: ZerionClient,
+    solana: Arc<SolanaRpc>,
+    token_metadata: Arc<TokenMetadataResolver>,
+}
+
+impl Default for WalletService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WalletService {
+    pub fn new() -> Self {
+        let solana = Arc::new(SolanaRpc::default());
+        Self::with_services(
+            Arc::clone(&solana),
+            Arc::new(TokenMetadataResolver::new(solana)),
+        )
+    }
+
+    /// Shares the RPC client and the metadata cache with the rest of the
+    /// server.
+    pub fn with_services(
+        solana: Arc<SolanaRpc>,
+        token_metadata: Arc<TokenMetadataResolver>,
+    ) -> Self {
+        Self {
+            zerion_client: ZerionClient::new(),
+            solana,
+            token_metadata,
+        }
+    }
+
+    /// Token positions on every chain Zerion indexes, or the Solana balances
+    /// when Zerion is down or knows nothing of the address.
+    pub async fn get_wallet_assets(&self, address: &str) -> Result<Vec<AssetsRow>, AppError> {
+        match self.get_detailed_positions(address).await {
+            Ok(rows) if !rows.is_empty() => Ok(rows),
+            _ => self.get_solana_assets(address).await,
+        }
+    }
+
+    /// One row per token, chain and position type.
+    async fn get_detailed_positions(&self, address: &str) -> Result<Vec<AssetsRow>, AppError> {
+        let positions = self.zerion_client.positions(address).await?;
+        Ok(positions.into_iter().map(AssetsRow::from).collect())
+    }
+
+    /// SOL and every SPL and Token-2022 balance, named after the mint
+    /// metadata. Values are left to the caller, which prices rows by
+    /// [`AssetsRow::price_key`].
+    async fn get_solana_assets(&self, address: &str) -> Result<Vec<AssetsRow>, AppError> {
+        let owner = Pubkey::from_str(address)
+            .map_err(|_| AppError::InvalidWalletAddress(address.to_string()))?;
+        let (lamport_balance, mut tokens) = tokio::try_join!(
+            async {
+                LamportBalance::get(&self.solana, address.to_string())
+                    .await
+                    .map_err(|_| AppError::SolanaRpcErr)
+            },
+            spl::token_balances(self.solana.client(), &owner),
+        )?;
+
+        let mints: Vec<String> = tokens.iter().map(|token| token.mint.clone()).collect();
+        let metadata = self.token_metadata.resolve(&mints).await;
+        for token in &mut tokens {
+            if let Some(metadata) = metadata.get(&token.mint) {
+                token.decimals = metadata.decimals;
+            }
+        }
+
+        let wrapped_sol = self.token_metadata.listed(oracle::WRAPPED_SOL_MINT);
+        let mut rows = vec![AssetsRow {
+            asset: "SOL".to_string(),
+            balance: format!("{:.6}", lamport_balance.to_sol()),
+            value: String::new(),
+            key: Some(AssetKey::symbol("SOL")),
+            mint: None,
+            name: Some("Solana".to_string()),
+            decimals: Some(9),
+            logo_uri: wrapped_sol.and_then(|token| token.logo_uri.clone()),
+            chain: Some("solana".to_string()),
+            position_type: Some(PositionType::Wallet),
+            price: None,
+        }];
+        rows.extend(tokens.iter().map(|token| {
+            let metadata = metadata.get(&token.mint);
+            AssetsRow {
+                asset: metadata.map_or(token.mint.clone(), |metadata| metadata.symbol.clone()),
+                balance: format!("{:.*}", token.decimals as usize, token.ui_amount()),
+                value: String::new(),
+                key: Some(token.key()),
+                mint: Some(token.mint.clone()),
+                name: metadata.map(|metadata| metadata.name.clone()),
+                decimals: Some(token.decimals),
+                logo_uri: metadata.and_then(|metadata| metadata.logo_uri.clone()),
+                chain: Some("solana".to_string()),
+                position_type: Some(PositionType::Wallet),
+                price: None,
+            }
+        }));
+        Ok(rows)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+pub struct LamportBalance(u64);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PolymarketSolana260 {
+    #[serde(serialize_with = "rfc3339")]
+    pub last_updated: std::time::SystemTime,
+    pub answer_no_multiplier: f64,
+    /// CLOB token of the "No" outcome, known after the first update.
+    pub no_token_id: Option<String>,
+}
+
+impl PolymarketSolana260 {
+    pub const SLUG: &str = "will-solana-reach-260-before-2026-327-264-879-598";
+
+    pub fn new() -> Self {
+        Self {
+            last_updated: std::time::SystemTime::UNIX_EPOCH,
+            answer_no_multiplier: 0.0,
+            no_token_id: None,
+        }
+    }
+
+    pub async fn update() -> Result<f64, AppError> {
+        Ok(Self::fetch().await?.answer_no_multiplier)
+    }
+
+    pub async fn fetch() -> Result<Self, AppError> {
+        let market = PolymarketClient::new()
+            .get_market_by_slug(Self::SLUG)
+            .await?;
+
+        let no_price = market.no_price().ok_or(AppError::PolymarketApiErr)?;
+        let no_token_id = market
+            .outcomes
+            .iter()
+            .position(|outcome| outcome.eq_ignore_ascii_case("No"))
+            .and_then(|i| market.clob_token_ids.get(i).cloned());
+
+        Ok(Self {
+            last_updated: std::time::SystemTime::now(),
+            answer_no_multiplier: no_price + sizing::POLYMARKET_FEE,
+            no_token_id,
+        })
+    }
+
+    /// Reprices "No" from a live quote, at the ask since that is what a buyer pays.
+    /// Returns whether anything changed.
+    pub fn apply_quote(&mut self, quote: &TokenQuote) -> bool {
+        match quote.best_ask {
+            Some(ask) => {
+                self.answer_no_multiplier = ask + sizing::POLYMARKET_FEE;
+                self.last_updated = quote.last_updated;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Default for PolymarketSolana260 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LamportBalance {
+    pub fn to_usd(&self, sol_to_usd: f64) -> f64 {
+        let self_sol = self.to_sol();
+        self_sol * sol_to_usd
+    }
+    pub fn to_sol(&self) -> f64 {
+        self.0 as f64 / 1_000_000_000.0
+    }
+    pub fn lamports(&self) -> u64 {
+        self.0
+    }
+    pub async fn get(solana: &SolanaRpc, wallet_address: String) -> Result<Self, AppError> {
+        let pubkey = Pubkey::from_str(&wallet_address)
+            .map_err(|_| AppError::InvalidWalletAddress(wallet_address))?;
+
+        let balance = solana
+            .client()
+            .get_balance(&pubkey)
+            .await
+            .map_err(|_| AppError::ErrorFetchingBalance)?;
+        Ok(LamportBalance(balance))
+    }
+}
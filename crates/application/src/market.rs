@@ -0,0 +1,136 @@
+use crate::AppError;
+use crate::kalshi::KalshiClient;
+use crate::polymarket::PolymarketClient;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+/// Spot assets we can hedge with prediction markets.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Asset {
+    Sol,
+    Btc,
+    Eth,
+}
+
+impl Asset {
+    pub const ALL: [Asset; 3] = [Asset::Sol, Asset::Btc, Asset::Eth];
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Asset::Sol => "SOL",
+            Asset::Btc => "BTC",
+            Asset::Eth => "ETH",
+        }
+    }
+}
+
+impl FromStr for Asset {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sol" | "solana" => Ok(Asset::Sol),
+            "btc" | "bitcoin" => Ok(Asset::Btc),
+            "eth" | "ethereum" => Ok(Asset::Eth),
+            _ => Err(()),
+        }
+    }
+}
+
+impl std::fmt::Display for Asset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Venue {
+    Polymarket,
+    Kalshi,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MarketStatus {
+    Active,
+    Closed,
+    Archived,
+}
+
+/// Venue independent view of a binary prediction market.
+///
+/// `id` is the Polymarket slug or the Kalshi ticker. Prices are in dollars
+/// per share, so a binary outcome always pays out `1.0`.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Market {
+    pub venue: Venue,
+    pub id: String,
+    pub question: String,
+    pub rules: String,
+    pub outcomes: Vec<String>,
+    pub outcome_prices: Vec<f64>,
+    /// Polymarket CLOB token ids in the same order as `outcomes`, empty for Kalshi.
+    pub token_ids: Vec<String>,
+    pub volume: f64,
+    pub liquidity: f64,
+    pub end_date: Option<DateTime<Utc>>,
+    pub status: MarketStatus,
+}
+
+impl Market {
+    pub fn outcome_price(&self, outcome: &str) -> Option<f64> {
+        self.outcomes
+            .iter()
+            .position(|o| o.eq_ignore_ascii_case(outcome))
+            .and_then(|i| self.outcome_prices.get(i).copied())
+    }
+
+    pub fn yes_price(&self) -> Option<f64> {
+        self.outcome_price("Yes")
+    }
+
+    pub fn no_price(&self) -> Option<f64> {
+        self.outcome_price("No")
+    }
+
+    /// Fetches the live market from its venue, `id` being a slug or a ticker.
+    pub async fn fetch(venue: Venue, id: &str) -> Result<Self, AppError> {
+        match venue {
+            Venue::Polymarket => Ok(PolymarketClient::new().get_market_by_slug(id).await?.into()),
+            Venue::Kalshi => Ok(KalshiClient::new().get_market(id).await?.into()),
+        }
+    }
+
+    pub fn url(&self) -> String {
+        match self.venue {
+            Venue::Polymarket => format!("https://polymarket.com/market/{}", self.id),
+            Venue::Kalshi => format!("https://kalshi.com/markets/{}", self.id),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Market {
+    /// Active Polymarket YES/NO market with YES at `yes`.
+    pub(crate) fn binary(id: &str, question: &str, yes: f64) -> Self {
+        Self {
+            venue: Venue::Polymarket,
+            id: id.to_string(),
+            question: question.to_string(),
+            rules: String::new(),
+            outcomes: vec!["Yes".to_string(), "No".to_string()],
+            outcome_prices: vec![yes, 1.0 - yes],
+            token_ids: Vec::new(),
+            volume: 0.0,
+            liquidity: 0.0,
+            end_date: None,
+            status: MarketStatus::Active,
+        }
+    }
+}
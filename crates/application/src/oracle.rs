@@ -0,0 +1,699 @@
+use crate::AppError;
+use crate::coingecko::{CoinGeckoClient, CoinIds};
+use crate::prices::{AssetKey, DEFAULT_COINS};
+use crate::pyth::PythOnChainSource;
+use crate::solana::SolanaRpc;
+use async_trait::async_trait;
+use futures_util::future::join_all;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use utoipa::ToSchema;
+
+pub const BINANCE_API_URL: &str = "https://api.binance.com/api/v3";
+pub const JUPITER_PRICE_URL: &str = "https://lite-api.jup.ag/price/v3";
+pub const HERMES_API_URL: &str = "https://hermes.pyth.network";
+
+pub const WRAPPED_SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+/// Pyth price feed ids of the assets we always track.
+pub const PYTH_FEEDS: [(&str, &str); 3] = [
+    (
+        "SOL",
+        "ef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d",
+    ),
+    (
+        "BTC",
+        "e62df6c8b4a85fe1a67db44dc12de5db330f7ac66b72dc658afedf0f4a415b43",
+    ),
+    (
+        "ETH",
+        "ff61491a931112ddf1bd8147cd1b641375f79f5825126d665480874634fd0ace",
+    ),
+];
+
+/// A place USD prices can be read from.
+#[async_trait]
+pub trait PriceSource: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Prices of the `assets` this source knows, the others are left out.
+    async fn prices(&self, assets: &[AssetKey]) -> Result<HashMap<AssetKey, f64>, AppError>;
+}
+
+/// Price agreed on by the sources that quoted an asset.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct AggregatedPrice {
+    pub usd: f64,
+    /// Sources the price is the median of.
+    pub sources: Vec<String>,
+    /// Sources that quoted the asset too far from the others.
+    pub dropped: Vec<String>,
+}
+
+/// Median of `quotes`, after dropping the ones further than `max_deviation`
+/// (a fraction, `0.02` is 2%) from the median of all of them. When no quote
+/// is close to that median, as with two sources that disagree, the first
+/// quote is kept: quotes come in order of preference.
+pub fn aggregate(quotes: &[(&str, f64)], max_deviation: f64) -> Option<AggregatedPrice> {
+    let healthy: Vec<(&str, f64)> = quotes
+        .iter()
+        .copied()
+        .filter(|(_, price)| price.is_finite() && *price > 0.0)
+        .collect();
+    let center = median(healthy.iter().map(|(_, price)| *price))?;
+
+    let (mut kept, mut dropped): (Vec<_>, Vec<_>) = healthy
+        .into_iter()
+        .partition(|(_, price)| ((price - center) / center).abs() <= max_deviation);
+    if kept.is_empty() {
+        kept.push(dropped.remove(0));
+    }
+
+    Some(AggregatedPrice {
+        usd: median(kept.iter().map(|(_, price)| *price))?,
+        sources: kept.iter().map(|(name, _)| name.to_string()).collect(),
+        dropped: dropped.iter().map(|(name, _)| name.to_string()).collect(),
+    })
+}
+
+fn median(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let mut values: Vec<f64> = values.collect();
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    })
+}
+
+#[derive(Debug, Default)]
+pub struct OracleReport {
+    pub prices: HashMap<AssetKey, AggregatedPrice>,
+    /// Sources that returned an error or timed out this round.
+    pub failed: Vec<&'static str>,
+}
+
+/// Asks every source at once and aggregates what came back.
+pub struct PriceOracle {
+    sources: Vec<Box<dyn PriceSource>>,
+    pub max_deviation: f64,
+    /// How long a source may take before it counts as failed.
+    pub timeout: Duration,
+}
+
+impl Default for PriceOracle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PriceOracle {
+    /// CoinGecko, Binance, Jupiter, Pyth Hermes and the Pyth accounts on
+    /// mainnet, with the default coins.
+    pub fn new() -> Self {
+        Self::with_coins(DEFAULT_COINS, Arc::new(SolanaRpc::default()))
+    }
+
+    /// Public sources, with CoinGecko ids for the given `(symbol, id)` pairs
+    /// and the Pyth accounts read through `solana`.
+    pub fn with_coins(
+        coins: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+        solana: Arc<SolanaRpc>,
+    ) -> Self {
+        Self::with_sources(vec![
+            Box::new(CoinGeckoSource::with_coins(CoinGeckoClient::new(), coins)),
+            Box::new(BinanceSource::new()),
+            Box::new(JupiterSource::new()),
+            Box::new(PythHermesSource::new()),
+            Box::new(PythOnChainSource::new(solana)),
+        ])
+    }
+
+    pub fn with_sources(sources: Vec<Box<dyn PriceSource>>) -> Self {
+        Self {
+            sources,
+            max_deviation: 0.02,
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    pub fn push(&mut self, source: Box<dyn PriceSource>) {
+        self.sources.push(source);
+    }
+
+    pub async fn prices(&self, assets: &[AssetKey]) -> OracleReport {
+        let answers = join_all(
+            self.sources
+                .iter()
+                .map(|source| tokio::time::timeout(self.timeout, source.prices(assets))),
+        )
+        .await;
+
+        let mut report = OracleReport::default();
+        let mut quotes: HashMap<&AssetKey, Vec<(&str, f64)>> = HashMap::new();
+        for (source, answer) in self.sources.iter().zip(&answers) {
+            match answer {
+                Ok(Ok(prices)) => {
+                    for (key, price) in prices {
+                        quotes.entry(key).or_default().push((source.name(), *price));
+                    }
+                }
+                Ok(Err(_)) | Err(_) => report.failed.push(source.name()),
+            }
+        }
+
+        report.prices = quotes
+            .into_iter()
+            .filter_map(|(key, quotes)| {
+                Some((key.clone(), aggregate(&quotes, self.max_deviation)?))
+            })
+            .collect();
+        report
+    }
+}
+
+pub(crate) fn symbols(assets: &[AssetKey]) -> impl Iterator<Item = &str> {
+    assets.iter().filter_map(|key| match key {
+        AssetKey::Symbol { symbol } => Some(symbol.as_str()),
+        _ => None,
+    })
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(
+    client: &Client,
+    url: &str,
+    query: &[(&str, &str)],
+) -> Result<T, AppError> {
+    let response = client
+        .get(url)
+        .query(query)
+        .header("accept", "application/json")
+        .send()
+        .await
+        .map_err(|_| AppError::ExchangePriceApiErr)?;
+
+    if !response.status().is_success() {
+        return Err(AppError::ExchangePriceApiErr);
+    }
+
+    response
+        .json::<T>()
+        .await
+        .map_err(|_| AppError::ExchangePriceApiErr)
+}
+
+/// Symbols through `simple/price`, mints and contracts through `token_price`.
+pub struct CoinGeckoSource {
+    client: CoinGeckoClient,
+    coin_ids: CoinIds,
+}
+
+impl CoinGeckoSource {
+    pub fn with_coins(
+        client: CoinGeckoClient,
+        coins: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Self {
+        Self {
+            client,
+            coin_ids: CoinIds::with_coins(coins),
+        }
+    }
+}
+
+#[async_trait]
+impl PriceSource for CoinGeckoSource {
+    fn name(&self) -> &'static str {
+        "coingecko"
+    }
+
+    /// One `simple/price` call for all symbols and one `token_price` call
+    /// per platform. Symbols never seen before are searched for first, one
+    /// whose search fails is left out this round.
+    async fn prices(&self, assets: &[AssetKey]) -> Result<HashMap<AssetKey, f64>, AppError> {
+        let mut ids = BTreeMap::new();
+        for symbol in symbols(assets) {
+            match self.coin_ids.resolve(&self.client, symbol).await {
+                Ok(Some(id)) => {
+                    ids.insert(id, AssetKey::symbol(symbol));
+                }
+                Ok(None) => {}
+                Err(err) => eprintln!("CoinGecko search for {symbol} failed: {err}"),
+            }
+        }
+
+        let mut by_platform: BTreeMap<&str, Vec<(&AssetKey, &str)>> = BTreeMap::new();
+        for key in assets {
+            match key {
+                AssetKey::SolanaMint { mint } => {
+                    by_platform.entry("solana").or_default().push((key, mint))
+                }
+                AssetKey::Evm { platform, address } => by_platform
+                    .entry(platform)
+                    .or_default()
+                    .push((key, address)),
+                AssetKey::Symbol { .. } => {}
+            }
+        }
+
+        let id_list: Vec<&str> = ids.keys().map(String::as_str).collect();
+        let mut prices: HashMap<AssetKey, f64> = self
+            .client
+            .simple_price(&id_list)
+            .await?
+            .into_iter()
+            .filter_map(|(id, price)| Some((ids.get(&id)?.clone(), price)))
+            .collect();
+
+        for (platform, tokens) in by_platform {
+            let addresses: Vec<&str> = tokens.iter().map(|(_, address)| *address).collect();
+            let by_address = self.client.token_price(platform, &addresses).await?;
+            prices.extend(tokens.into_iter().filter_map(|(key, address)| {
+                let (_, price) = by_address
+                    .iter()
+                    .find(|(a, _)| a.eq_ignore_ascii_case(address))?;
+                Some((key.clone(), *price))
+            }));
+        }
+
+        Ok(prices)
+    }
+}
+
+/// Binance spot tickers against USDT, symbols only.
+pub struct BinanceSource {
+    client: Client,
+    base_url: String,
+}
+
+impl Default for BinanceSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BinanceSource {
+    pub fn new() -> Self {
+        Self::with_base_url(BINANCE_API_URL)
+    }
+
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceTicker {
+    symbol: String,
+    price: String,
+}
+
+#[async_trait]
+impl PriceSource for BinanceSource {
+    fn name(&self) -> &'static str {
+        "binance"
+    }
+
+    /// Reads the whole ticker list, asking for a pair Binance does not
+    /// list would fail the request for all of them.
+    async fn prices(&self, assets: &[AssetKey]) -> Result<HashMap<AssetKey, f64>, AppError> {
+        let wanted: Vec<&str> = symbols(assets).collect();
+        if wanted.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let url = format!("{}/ticker/price", self.base_url);
+        let tickers: Vec<BinanceTicker> = get_json(&self.client, &url, &[]).await?;
+        let by_pair: HashMap<&str, &str> = tickers
+            .iter()
+            .map(|ticker| (ticker.symbol.as_str(), ticker.price.as_str()))
+            .collect();
+
+        Ok(wanted
+            .into_iter()
+            .filter_map(|symbol| {
+                let price = by_pair
+                    .get(format!("{symbol}USDT").as_str())?
+                    .parse()
+                    .ok()?;
+                Some((AssetKey::symbol(symbol), price))
+            })
+            .collect())
+    }
+}
+
+/// Jupiter price API, Solana mints only. `SOL` is read as wrapped SOL.
+pub struct JupiterSource {
+    client: Client,
+    base_url: String,
+}
+
+impl Default for JupiterSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JupiterSource {
+    pub fn new() -> Self {
+        Self::with_base_url(JUPITER_PRICE_URL)
+    }
+
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JupiterPrice {
+    #[serde(rename = "usdPrice")]
+    usd_price: f64,
+}
+
+#[async_trait]
+impl PriceSource for JupiterSource {
+    fn name(&self) -> &'static str {
+        "jupiter"
+    }
+
+    async fn prices(&self, assets: &[AssetKey]) -> Result<HashMap<AssetKey, f64>, AppError> {
+        let mints: Vec<(&AssetKey, &str)> = assets
+            .iter()
+            .filter_map(|key| match key {
+                AssetKey::SolanaMint { mint } => Some((key, mint.as_str())),
+                AssetKey::Symbol { symbol } if symbol == "SOL" => Some((key, WRAPPED_SOL_MINT)),
+                _ => None,
+            })
+            .collect();
+        if mints.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let ids: Vec<&str> = mints.iter().map(|(_, mint)| *mint).collect();
+        let prices: HashMap<String, Option<JupiterPrice>> =
+            get_json(&self.client, &self.base_url, &[("ids", &ids.join(","))]).await?;
+
+        Ok(mints
+            .into_iter()
+            .filter_map(|(key, mint)| {
+                let price = prices.get(mint)?.as_ref()?;
+                Some((key.clone(), price.usd_price))
+            })
+            .collect())
+    }
+}
+
+/// Pyth prices through the Hermes HTTP API, symbols only.
+pub struct PythHermesSource {
+    client: Client,
+    base_url: String,
+    /// Feed id by symbol, `None` when Hermes has no USD feed for it.
+    feed_ids: Mutex<HashMap<String, Option<String>>>,
+}
+
+impl Default for PythHermesSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PythHermesSource {
+    pub fn new() -> Self {
+        Self::with_base_url(HERMES_API_URL)
+    }
+
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        let feed_ids = PYTH_FEEDS
+            .iter()
+            .map(|(symbol, id)| (symbol.to_string(), Some(id.to_string())))
+            .collect();
+        Self {
+            client: Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            feed_ids: Mutex::new(feed_ids),
+        }
+    }
+
+    async fn feed_id(&self, symbol: &str) -> Result<Option<String>, AppError> {
+        if let Some(id) = self.feed_ids.lock().unwrap().get(symbol) {
+            return Ok(id.clone());
+        }
+
+        let url = format!("{}/v2/price_feeds", self.base_url);
+        let feeds: Vec<HermesFeed> = get_json(
+            &self.client,
+            &url,
+            &[("query", symbol), ("asset_type", "crypto")],
+        )
+        .await?;
+        let id = feeds
+            .into_iter()
+            .find(|feed| {
+                feed.attributes.base.eq_ignore_ascii_case(symbol)
+                    && feed.attributes.quote_currency == "USD"
+            })
+            .map(|feed| feed.id);
+
+        self.feed_ids
+            .lock()
+            .unwrap()
+            .insert(symbol.to_string(), id.clone());
+        Ok(id)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct HermesFeed {
+    id: String,
+    attributes: HermesAttributes,
+}
+
+#[derive(Debug, Deserialize)]
+struct HermesAttributes {
+    #[serde(default)]
+    base: String,
+    #[serde(default)]
+    quote_currency: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HermesUpdate {
+    #[serde(default)]
+    parsed: Vec<HermesParsed>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HermesParsed {
+    id: String,
+    price: HermesPrice,
+}
+
+#[derive(Debug, Deserialize)]
+struct HermesPrice {
+    price: String,
+    expo: i32,
+}
+
+#[async_trait]
+impl PriceSource for PythHermesSource {
+    fn name(&self) -> &'static str {
+        "pyth"
+    }
+
+    /// Feed ids not known yet are searched for all at once, a symbol whose
+    /// search fails is left out this round.
+    async fn prices(&self, assets: &[AssetKey]) -> Result<HashMap<AssetKey, f64>, AppError> {
+        let lookups = join_all(
+            symbols(assets).map(|symbol| async move { (symbol, self.feed_id(symbol).await) }),
+        )
+        .await;
+        let mut feeds = HashMap::new();
+        for (symbol, lookup) in lookups {
+            match lookup {
+                Ok(Some(id)) => {
+                    feeds.insert(id, AssetKey::symbol(symbol));
+                }
+                Ok(None) => {}
+                Err(err) => eprintln!("Pyth feed search for {symbol} failed: {err}"),
+            }
+        }
+        if feeds.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let url = format!("{}/v2/updates/price/latest", self.base_url);
+        let mut query: Vec<(&str, &str)> = feeds.keys().map(|id| ("ids[]", id.as_str())).collect();
+        query.push(("parsed", "true"));
+        let update: HermesUpdate = get_json(&self.client, &url, &query).await?;
+
+        Ok(update
+            .parsed
+            .into_iter()
+            .filter_map(|parsed| {
+                let key = feeds.get(parsed.id.trim_start_matches("0x"))?;
+                let mantissa: f64 = parsed.price.price.parse().ok()?;
+                Some((key.clone(), mantissa * 10f64.powi(parsed.price.expo)))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn median_of_agreeing_sources() {
+        let price = aggregate(&[("a", 100.0), ("b", 101.0), ("c", 99.5)], 0.02).unwrap();
+        assert_eq!(price.usd, 100.0);
+        assert_eq!(price.sources, ["a", "b", "c"]);
+        assert!(price.dropped.is_empty());
+    }
+
+    #[test]
+    fn drops_outliers() {
+        let price = aggregate(
+            &[("a", 100.0), ("b", 100.4), ("c", 130.0), ("d", 99.8)],
+            0.02,
+        )
+        .unwrap();
+        assert_eq!(price.usd, 100.0);
+        assert_eq!(price.dropped, ["c"]);
+    }
+
+    #[test]
+    fn ignores_unusable_quotes() {
+        assert!(aggregate(&[("a", 0.0), ("b", f64::NAN)], 0.02).is_none());
+        let price = aggregate(&[("a", 0.0), ("b", 42.0)], 0.02).unwrap();
+        assert_eq!(price.usd, 42.0);
+        assert_eq!(price.sources, ["b"]);
+    }
+
+    #[test]
+    fn two_disagreeing_quotes_keep_the_first() {
+        let price = aggregate(&[("a", 100.0), ("b", 150.0)], 0.02).unwrap();
+        assert_eq!(price.usd, 100.0);
+        assert_eq!(price.sources, ["a"]);
+        assert_eq!(price.dropped, ["b"]);
+
+        let price = aggregate(&[("a", 100.0), ("b", 101.0)], 0.02).unwrap();
+        assert_eq!(price.usd, 100.5);
+        assert_eq!(price.sources, ["a", "b"]);
+    }
+
+    struct Fixed(&'static str, Result<f64, ()>);
+
+    #[async_trait]
+    impl PriceSource for Fixed {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        async fn prices(&self, assets: &[AssetKey]) -> Result<HashMap<AssetKey, f64>, AppError> {
+            let price = self.1.map_err(|_| AppError::ExchangePriceApiErr)?;
+            Ok(assets.iter().map(|key| (key.clone(), price)).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn oracle_reports_failed_sources() {
+        let oracle = PriceOracle::with_sources(vec![
+            Box::new(Fixed("up", Ok(10.0))),
+            Box::new(Fixed("down", Err(()))),
+        ]);
+        let sol = AssetKey::symbol("SOL");
+        let report = oracle.prices(std::slice::from_ref(&sol)).await;
+
+        assert_eq!(report.prices[&sol].usd, 10.0);
+        assert_eq!(report.prices[&sol].sources, ["up"]);
+        assert_eq!(report.failed, ["down"]);
+    }
+
+    struct Stuck;
+
+    #[async_trait]
+    impl PriceSource for Stuck {
+        fn name(&self) -> &'static str {
+            "stuck"
+        }
+
+        async fn prices(&self, _: &[AssetKey]) -> Result<HashMap<AssetKey, f64>, AppError> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn slow_sources_time_out() {
+        let mut oracle =
+            PriceOracle::with_sources(vec![Box::new(Fixed("up", Ok(10.0))), Box::new(Stuck)]);
+        oracle.timeout = Duration::from_millis(50);
+        let sol = AssetKey::symbol("SOL");
+        let report = oracle.prices(std::slice::from_ref(&sol)).await;
+
+        assert_eq!(report.prices[&sol].sources, ["up"]);
+        assert_eq!(report.failed, ["stuck"]);
+    }
+
+    #[tokio::test]
+    async fn hermes_skips_symbols_it_cannot_search() {
+        let stand_in = crate::stand_in::StandIn::bind().await;
+        let source = PythHermesSource::with_base_url(&stand_in.url);
+        let server = stand_in.serve(vec![
+            (500, String::new()),
+            (
+                200,
+                format!(
+                    r#"{{"parsed": [{{"id": "{}", "price": {{"price": "18000000000", "expo": -8}}}}]}}"#,
+                    PYTH_FEEDS[0].1
+                ),
+            ),
+        ]);
+
+        let prices = source
+            .prices(&[AssetKey::symbol("NEW"), AssetKey::symbol("SOL")])
+            .await
+            .unwrap();
+
+        assert_eq!(prices.len(), 1);
+        assert_eq!(prices[&AssetKey::symbol("SOL")], 180.0);
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("GET /v2/price_feeds?query=NEW&"));
+        assert!(requests[1].starts_with("GET /v2/updates/price/latest?"));
+    }
+
+    #[tokio::test]
+    async fn coingecko_skips_symbols_it_cannot_search() {
+        let stand_in = crate::stand_in::StandIn::bind().await;
+        let source = CoinGeckoSource::with_coins(
+            CoinGeckoClient::with_base_url(&stand_in.url),
+            [("SOL", "solana")],
+        );
+        let server = stand_in.serve(vec![
+            (500, String::new()),
+            (200, r#"{"solana": {"usd": 180.0}}"#.to_string()),
+        ]);
+
+        let prices = source
+            .prices(&[AssetKey::symbol("NEW"), AssetKey::symbol("SOL")])
+            .await
+            .unwrap();
+
+        assert_eq!(prices.len(), 1);
+        assert_eq!(prices[&AssetKey::symbol("SOL")], 180.0);
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("GET /search?query=NEW "));
+        assert!(requests[1].starts_with("GET /simple/price?ids=solana&"));
+    }
+}
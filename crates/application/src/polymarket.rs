@@ -0,0 +1,371 @@
+use crate::AppError;
+use crate::market::{Market, MarketStatus, Venue};
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Deserializer, Serialize};
+
+pub const GAMMA_API_URL: &str = "https://gamma-api.polymarket.com";
+
+/// Market as returned by the Gamma API, with the stringified arrays decoded.
+#[derive(Debug, Clone, Serialize)]
+pub struct PolymarketMarket {
+    pub id: String,
+    pub slug: String,
+    pub condition_id: String,
+    pub question: String,
+    pub description: String,
+    pub outcomes: Vec<String>,
+    pub outcome_prices: Vec<f64>,
+    pub clob_token_ids: Vec<String>,
+    pub volume: f64,
+    pub liquidity: f64,
+    pub end_date: Option<DateTime<Utc>>,
+    pub status: MarketStatus,
+}
+
+impl PolymarketMarket {
+    /// Price of the outcome with the given label, e.g. `"Yes"` or `"No"`.
+    pub fn outcome_price(&self, outcome: &str) -> Option<f64> {
+        self.outcomes
+            .iter()
+            .position(|o| o.eq_ignore_ascii_case(outcome))
+            .and_then(|i| self.outcome_prices.get(i).copied())
+    }
+
+    pub fn yes_price(&self) -> Option<f64> {
+        self.outcome_price("Yes")
+    }
+
+    pub fn no_price(&self) -> Option<f64> {
+        self.outcome_price("No")
+    }
+}
+
+impl From<PolymarketMarket> for Market {
+    fn from(market: PolymarketMarket) -> Self {
+        Self {
+            venue: Venue::Polymarket,
+            id: market.slug,
+            question: market.question,
+            rules: market.description,
+            outcomes: market.outcomes,
+            outcome_prices: market.outcome_prices,
+            token_ids: market.clob_token_ids,
+            volume: market.volume,
+            liquidity: market.liquidity,
+            end_date: market.end_date,
+            status: market.status,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GammaMarket {
+    id: String,
+    #[serde(default)]
+    slug: String,
+    #[serde(default)]
+    condition_id: String,
+    #[serde(default)]
+    question: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default, deserialize_with = "stringified_vec")]
+    outcomes: Vec<String>,
+    #[serde(default, deserialize_with = "stringified_vec")]
+    outcome_prices: Vec<String>,
+    #[serde(default, deserialize_with = "stringified_vec")]
+    clob_token_ids: Vec<String>,
+    #[serde(default)]
+    volume_num: Option<f64>,
+    #[serde(default)]
+    liquidity_num: Option<f64>,
+    #[serde(default)]
+    end_date: Option<DateTime<Utc>>,
+    #[serde(default)]
+    active: bool,
+    #[serde(default)]
+    closed: bool,
+    #[serde(default)]
+    archived: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct GammaEvent {
+    id: String,
+    #[serde(default)]
+    slug: String,
+    #[serde(default)]
+    title: String,
+    /// Parsed one by one, so a malformed market does not lose the event.
+    #[serde(default)]
+    markets: Vec<serde_json::Value>,
+}
+
+/// Event grouping several markets, e.g. one market per strike.
+#[derive(Debug, Clone, Serialize)]
+pub struct PolymarketEvent {
+    pub id: String,
+    pub slug: String,
+    pub title: String,
+    pub markets: Vec<PolymarketMarket>,
+}
+
+impl From<GammaEvent> for PolymarketEvent {
+    fn from(raw: GammaEvent) -> Self {
+        Self {
+            id: raw.id,
+            slug: raw.slug,
+            title: raw.title,
+            markets: raw.markets.into_iter().filter_map(parse_market).collect(),
+        }
+    }
+}
+
+/// One page of a Gamma listing. `returned` counts every entry the API sent,
+/// parsed or not, so a short page can be told from a page with bad entries.
+#[derive(Debug, Clone)]
+pub struct GammaPage<T> {
+    pub items: Vec<T>,
+    pub returned: usize,
+}
+
+fn parse_market(raw: serde_json::Value) -> Option<PolymarketMarket> {
+    serde_json::from_value::<GammaMarket>(raw)
+        .ok()?
+        .try_into()
+        .ok()
+}
+
+/// Gamma encodes `outcomes`, `outcomePrices` and `clobTokenIds` as JSON arrays
+/// inside a string, e.g. `"[\"Yes\", \"No\"]"`.
+fn stringified_vec<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = Option::<String>::deserialize(deserializer)?;
+    match raw {
+        Some(s) if !s.is_empty() => serde_json::from_str(&s).map_err(serde::de::Error::custom),
+        _ => Ok(Vec::new()),
+    }
+}
+
+impl TryFrom<GammaMarket> for PolymarketMarket {
+    type Error = AppError;
+
+    fn try_from(raw: GammaMarket) -> Result<Self, Self::Error> {
+        let outcome_prices = raw
+            .outcome_prices
+            .iter()
+            .map(|p| p.parse::<f64>().map_err(|_| AppError::PolymarketApiErr))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let status = if raw.archived {
+            MarketStatus::Archived
+        } else if raw.closed || !raw.active {
+            MarketStatus::Closed
+        } else {
+            MarketStatus::Active
+        };
+
+        Ok(Self {
+            id: raw.id,
+            slug: raw.slug,
+            condition_id: raw.condition_id,
+            question: raw.question,
+            description: raw.description,
+            outcomes: raw.outcomes,
+            outcome_prices,
+            clob_token_ids: raw.clob_token_ids,
+            volume: raw.volume_num.unwrap_or(0.0),
+            liquidity: raw.liquidity_num.unwrap_or(0.0),
+            end_date: raw.end_date,
+            status,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct PolymarketClient {
+    client: Client,
+    base_url: String,
+}
+
+impl Default for PolymarketClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PolymarketClient {
+    pub fn new() -> Self {
+        Self::with_base_url(GAMMA_API_URL)
+    }
+
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub async fn get_market_by_slug(&self, slug: &str) -> Result<PolymarketMarket, AppError> {
+        let url = format!("{}/markets/slug/{}", self.base_url, slug);
+        let raw: GammaMarket = self.get_json(&url).await?;
+        raw.try_into()
+    }
+
+    pub async fn get_market_by_condition_id(
+        &self,
+        condition_id: &str,
+    ) -> Result<PolymarketMarket, AppError> {
+        let url = format!("{}/markets?condition_ids={}", self.base_url, condition_id);
+        let raw: Vec<GammaMarket> = self.get_json(&url).await?;
+        raw.into_iter()
+            .next()
+            .ok_or(AppError::PolymarketApiErr)?
+            .try_into()
+    }
+
+    /// One page of open markets, `offset` counts markets not pages.
+    pub async fn list_markets(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<GammaPage<PolymarketMarket>, AppError> {
+        let url = format!(
+            "{}/markets?active=true&closed=false&limit={}&offset={}",
+            self.base_url, limit, offset
+        );
+        let raw: Vec<serde_json::Value> = self.get_json(&url).await?;
+        Ok(GammaPage {
+            returned: raw.len(),
+            items: raw.into_iter().filter_map(parse_market).collect(),
+        })
+    }
+
+    /// One page of open events, optionally restricted to a Gamma tag such as `crypto`.
+    pub async fn list_events(
+        &self,
+        tag_slug: Option<&str>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<GammaPage<PolymarketEvent>, AppError> {
+        let mut url = format!(
+            "{}/events?active=true&closed=false&limit={}&offset={}",
+            self.base_url, limit, offset
+        );
+        if let Some(tag_slug) = tag_slug {
+            url.push_str(&format!("&tag_slug={}", tag_slug));
+        }
+        let raw: Vec<serde_json::Value> = self.get_json(&url).await?;
+        Ok(GammaPage {
+            returned: raw.len(),
+            items: raw
+                .into_iter()
+                .filter_map(|event| serde_json::from_value::<GammaEvent>(event).ok())
+                .map(PolymarketEvent::from)
+                .collect(),
+        })
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T, AppError> {
+        let response = self
+            .client
+            .get(url)
+            .header("accept", "application/json")
+            .send()
+            .await
+            .map_err(|_| AppError::PolymarketApiErr)?;
+
+        if !response.status().is_success() {
+            return Err(AppError::PolymarketApiErr);
+        }
+
+        response
+            .json::<T>()
+            .await
+            .map_err(|_| AppError::PolymarketApiErr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Trimmed from `GET /markets/slug/...`, the arrays come as strings.
+    const GAMMA_MARKET: &str = r#"{
+        "id": "516710",
+        "question": "Will Solana reach $260 before 2026?",
+        "conditionId": "0x5f1c0a3c2d1e",
+        "slug": "will-solana-reach-260-before-2026-327-264-879-598",
+        "endDate": "2025-12-31T12:00:00Z",
+        "liquidity": "48211.0712",
+        "description": "This market will resolve to \"Yes\" if any Binance 1 minute candle for SOLUSDT has a final \"High\" price of $260 or higher.",
+        "outcomes": "[\"Yes\", \"No\"]",
+        "outcomePrices": "[\"0.115\", \"0.885\"]",
+        "volume": "1204388.52",
+        "active": true,
+        "closed": false,
+        "archived": false,
+        "clobTokenIds": "[\"7195432\", \"9988123\"]",
+        "volumeNum": 1204388.52,
+        "liquidityNum": 48211.0712
+    }"#;
+
+    fn market(json: &str) -> Result<PolymarketMarket, AppError> {
+        serde_json::from_str::<GammaMarket>(json)
+            .map_err(|_| AppError::PolymarketApiErr)?
+            .try_into()
+    }
+
+    #[test]
+    fn decodes_stringified_arrays() {
+        let market = market(GAMMA_MARKET).unwrap();
+        assert_eq!(market.outcomes, ["Yes", "No"]);
+        assert_eq!(market.outcome_prices, [0.115, 0.885]);
+        assert_eq!(market.clob_token_ids, ["7195432", "9988123"]);
+        assert_eq!(market.yes_price(), Some(0.115));
+        assert_eq!(market.no_price(), Some(0.885));
+        assert_eq!(market.volume, 1204388.52);
+        assert_eq!(market.status, MarketStatus::Active);
+        assert!(market.end_date.is_some());
+    }
+
+    #[test]
+    fn missing_or_empty_arrays_are_empty() {
+        let market = market(r#"{"id": "1", "outcomes": "", "clobTokenIds": null}"#).unwrap();
+        assert!(market.outcomes.is_empty());
+        assert!(market.outcome_prices.is_empty());
+        assert!(market.clob_token_ids.is_empty());
+        assert_eq!(market.yes_price(), None);
+        assert_eq!(market.status, MarketStatus::Closed);
+    }
+
+    #[test]
+    fn rejects_malformed_arrays() {
+        let not_json = GAMMA_MARKET.replace(r#""[\"Yes\", \"No\"]""#, r#""Yes, No""#);
+        assert!(market(&not_json).is_err());
+
+        let not_a_price = GAMMA_MARKET.replace(r#"\"0.885\""#, r#"\"n/a\""#);
+        assert!(market(&not_a_price).is_err());
+
+        let not_strings = GAMMA_MARKET.replace(r#""[\"0.115\", \"0.885\"]""#, "[0.115, 0.885]");
+        assert!(market(&not_strings).is_err());
+    }
+
+    #[test]
+    fn events_drop_markets_that_do_not_parse() {
+        let bad_price = GAMMA_MARKET.replace(r#"\"0.885\""#, r#"\"n/a\""#);
+        let not_json = GAMMA_MARKET.replace(r#""[\"Yes\", \"No\"]""#, r#""Yes, No""#);
+        let event: GammaEvent = serde_json::from_str(&format!(
+            r#"{{"id": "9", "slug": "sol-above", "title": "SOL above ...?", "markets": [{}, {}, {}]}}"#,
+            GAMMA_MARKET, bad_price, not_json
+        ))
+        .unwrap();
+        let event = PolymarketEvent::from(event);
+        assert_eq!(event.markets.len(), 1);
+        assert_eq!(event.markets[0].id, "516710");
+    }
+}
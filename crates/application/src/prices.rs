@@ -0,0 +1,377 @@
+use crate::AppError;
+use crate::oracle::{AggregatedPrice, PriceOracle};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, SystemTime};
+use utoipa::ToSchema;
+
+/// CoinGecko ids fetched when nothing else is configured, the chart's
+/// coins among them.
+pub const DEFAULT_COINS: [(&str, &str); 5] = [
+    ("SOL", "solana"),
+    ("BTC", "bitcoin"),
+    ("ETH", "ethereum"),
+    ("USDC", "usd-coin"),
+    ("MET", "meteora"),
+];
+
+/// How an asset is identified in a wallet.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AssetKey {
+    /// Uppercase ticker, `SOL`.
+    Symbol { symbol: String },
+    /// SPL token mint, base58 as is.
+    SolanaMint { mint: String },
+    /// Token contract on a CoinGecko platform (`ethereum`, `arbitrum-one`,
+    /// `base`, ...), address lowercased.
+    Evm { platform: String, address: String },
+}
+
+impl AssetKey {
+    pub fn symbol(symbol: &str) -> Self {
+        AssetKey::Symbol {
+            symbol: symbol.trim().to_uppercase(),
+        }
+    }
+
+    pub fn solana_mint(mint: &str) -> Self {
+        AssetKey::SolanaMint {
+            mint: mint.trim().to_string(),
+        }
+    }
+
+    pub fn evm(platform: &str, address: &str) -> Self {
+        AssetKey::Evm {
+            platform: platform.trim().to_lowercase(),
+            address: address.trim().to_lowercase(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PriceQuote {
+    pub key: AssetKey,
+    pub usd: f64,
+    /// Sources that agreed on `usd`.
+    pub sources: Vec<String>,
+    #[serde(serialize_with = "crate::rfc3339")]
+    #[schema(value_type = String, format = DateTime)]
+    pub last_updated: SystemTime,
+}
+
+/// Assets a refresh has to price, taken from the registry so the sources
+/// can be asked without holding its lock.
+#[derive(Debug, Clone)]
+pub struct PriceRequest {
+    keys: Vec<AssetKey>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PriceUpdate {
+    pub prices: Vec<(AssetKey, AggregatedPrice)>,
+    /// Assets no source knows, only filled in when every source answered.
+    pub unresolved: Vec<AssetKey>,
+    pub failed: Vec<&'static str>,
+}
+
+impl PriceRequest {
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Fails only when nothing could be priced and some source was down.
+    pub async fn fetch(self, oracle: &PriceOracle) -> Result<PriceUpdate, AppError> {
+        let mut report = oracle.prices(&self.keys).await;
+        if report.prices.is_empty() && !report.failed.is_empty() {
+            return Err(AppError::ExchangePriceApiErr);
+        }
+
+        let unresolved = if report.failed.is_empty() {
+            self.keys
+                .into_iter()
+                .filter(|key| !report.prices.contains_key(key))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Ok(PriceUpdate {
+            prices: report.prices.drain().collect(),
+            unresolved,
+            failed: report.failed,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Tracked {
+    /// Configured assets are never forgotten.
+    pinned: bool,
+    last_requested: SystemTime,
+}
+
+/// USD prices of every asset the server has been asked about.
+///
+/// Configured coins are always tracked. Other assets are learned with
+/// [`PriceRegistry::track`] the first time a wallet holds them and dropped
+/// once nobody asked for them within `forget_after`. Prices older than
+/// `max_age` are not served.
+#[derive(Debug, Clone)]
+pub struct PriceRegistry {
+    prices: HashMap<AssetKey, (AggregatedPrice, SystemTime)>,
+    tracked: HashMap<AssetKey, Tracked>,
+    /// Configured CoinGecko id by uppercase symbol, lets `get_price` accept ids.
+    coin_ids: BTreeMap<String, String>,
+    unresolved: HashMap<AssetKey, SystemTime>,
+    pub max_age: Duration,
+    pub forget_after: Duration,
+    pub last_updated: SystemTime,
+}
+
+impl Default for PriceRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PriceRegistry {
+    pub fn new() -> Self {
+        Self::with_coins(DEFAULT_COINS)
+    }
+
+    /// Tracks the given `(symbol, CoinGecko id)` pairs.
+    pub fn with_coins(
+        coins: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Self {
+        let coin_ids: BTreeMap<String, String> = coins
+            .into_iter()
+            .map(|(symbol, id)| (symbol.into().to_uppercase(), id.into()))
+            .collect();
+        let tracked = coin_ids
+            .keys()
+            .map(|symbol| {
+                let tracked = Tracked {
+                    pinned: true,
+                    last_requested: SystemTime::now(),
+                };
+                (AssetKey::symbol(symbol), tracked)
+            })
+            .collect();
+
+        Self {
+            prices: HashMap::new(),
+            tracked,
+            coin_ids,
+            unresolved: HashMap::new(),
+            max_age: Duration::from_secs(5 * 60),
+            forget_after: Duration::from_secs(24 * 60 * 60),
+            last_updated: SystemTime::UNIX_EPOCH,
+        }
+    }
+
+    /// Marks `keys` as wanted and returns the request for the ones seen for
+    /// the first time, so they can be priced right away.
+    pub fn track(&mut self, keys: impl IntoIterator<Item = AssetKey>) -> PriceRequest {
+        let now = SystemTime::now();
+        let mut new = Vec::new();
+        for key in keys {
+            if self.unresolved.contains_key(&key) {
+                continue;
+            }
+            self.tracked
+                .entry(key.clone())
+                .and_modify(|tracked| tracked.last_requested = now)
+                .or_insert_with(|| {
+                    new.push(key);
+                    Tracked {
+                        pinned: false,
+                        last_requested: now,
+                    }
+                });
+        }
+        self.request(new)
+    }
+
+    pub fn is_tracked(&self, key: &AssetKey) -> bool {
+        self.tracked.contains_key(key)
+    }
+
+    /// Request for everything tracked, what the periodic refresh fetches.
+    pub fn refresh_request(&self) -> PriceRequest {
+        self.request(self.tracked.keys().cloned().collect())
+    }
+
+    fn request(&self, keys: Vec<AssetKey>) -> PriceRequest {
+        PriceRequest { keys }
+    }
+
+    pub fn record(&mut self, update: PriceUpdate) {
+        let now = SystemTime::now();
+        for key in update.unresolved {
+            if self
+                .tracked
+                .get(&key)
+                .is_some_and(|tracked| !tracked.pinned)
+            {
+                self.tracked.remove(&key);
+                self.unresolved.insert(key, now);
+            }
+        }
+        for (key, price) in update.prices {
+            self.prices.insert(key, (price, now));
+        }
+        self.last_updated = now;
+    }
+
+    /// Drops prices older than `max_age` and learned assets nobody asked
+    /// for within `forget_after`. Unresolved assets are retried after that too.
+    pub fn expire(&mut self) {
+        let now = SystemTime::now();
+        let older_than =
+            |time: SystemTime, age: Duration| now.duration_since(time).unwrap_or_default() > age;
+
+        self.prices
+            .retain(|_, (_, updated)| !older_than(*updated, self.max_age));
+        self.tracked.retain(|_, tracked| {
+            tracked.pinned || !older_than(tracked.last_requested, self.forget_after)
+        });
+        self.unresolved
+            .retain(|_, since| !older_than(*since, self.forget_after));
+    }
+
+    pub fn get(&self, key: &AssetKey) -> Option<PriceQuote> {
+        let (price, last_updated) = self.prices.get(key)?;
+        let age = SystemTime::now()
+            .duration_since(*last_updated)
+            .unwrap_or_default();
+        (age <= self.max_age).then(|| PriceQuote {
+            key: key.clone(),
+            usd: price.usd,
+            sources: price.sources.clone(),
+            last_updated: *last_updated,
+        })
+    }
+
+    /// Accepts a symbol or a CoinGecko id in any case: `sol`, `SOL`, `solana`.
+    pub fn get_price(&self, symbol: &str) -> Option<f64> {
+        let wanted = symbol.to_uppercase();
+        if let Some(quote) = self.get(&AssetKey::symbol(&wanted)) {
+            return Some(quote.usd);
+        }
+        let (symbol, _) = self
+            .coin_ids
+            .iter()
+            .find(|(_, id)| id.eq_ignore_ascii_case(&wanted))?;
+        self.get(&AssetKey::symbol(symbol)).map(|quote| quote.usd)
+    }
+
+    pub fn get_sol_to_usd(&self) -> f64 {
+        self.get_price("SOL").unwrap_or(0.0)
+    }
+
+    /// Fresh prices, symbols first.
+    pub fn quotes(&self) -> Vec<PriceQuote> {
+        let mut quotes: Vec<PriceQuote> =
+            self.prices.keys().filter_map(|key| self.get(key)).collect();
+        quotes.sort_by(|a, b| a.key.cmp(&b.key));
+        quotes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(prices: &[(AssetKey, f64)]) -> PriceUpdate {
+        let prices = prices
+            .iter()
+            .map(|(key, usd)| {
+                let price = AggregatedPrice {
+                    usd: *usd,
+                    sources: vec!["test".to_string()],
+                    dropped: Vec::new(),
+                };
+                (key.clone(), price)
+            })
+            .collect();
+        PriceUpdate {
+            prices,
+            ..PriceUpdate::default()
+        }
+    }
+
+    #[test]
+    fn looks_up_symbols_and_coin_ids() {
+        let mut registry = PriceRegistry::new();
+        registry.record(update(&[(AssetKey::symbol("SOL"), 180.0)]));
+
+        assert_eq!(registry.get_price("sol"), Some(180.0));
+        assert_eq!(registry.get_price("Solana"), Some(180.0));
+        assert_eq!(registry.get_price("BTC"), None);
+    }
+
+    #[test]
+    fn quotes_serialize_timestamps_as_rfc3339() {
+        let quote = PriceQuote {
+            key: AssetKey::symbol("SOL"),
+            usd: 180.0,
+            sources: vec!["coingecko".to_string()],
+            last_updated: SystemTime::UNIX_EPOCH + Duration::from_millis(1_760_700_000_250),
+        };
+        let json = serde_json::to_value(&quote).unwrap();
+        assert_eq!(json["last_updated"], "2025-10-17T11:20:00.250Z");
+    }
+
+    #[test]
+    fn only_new_assets_need_fetching() {
+        let mut registry = PriceRegistry::new();
+        let mint = AssetKey::solana_mint("JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN");
+
+        assert!(registry.track([AssetKey::symbol("sol")]).is_empty());
+        assert_eq!(registry.track([mint.clone()]).keys, vec![mint.clone()]);
+        assert!(registry.track([mint]).is_empty());
+        assert_eq!(
+            registry.refresh_request().keys.len(),
+            DEFAULT_COINS.len() + 1
+        );
+    }
+
+    #[test]
+    fn stale_prices_are_not_served() {
+        let mut registry = PriceRegistry::new();
+        registry.record(update(&[(AssetKey::symbol("SOL"), 180.0)]));
+        registry.max_age = Duration::ZERO;
+        std::thread::sleep(Duration::from_millis(5));
+
+        assert_eq!(registry.get_price("SOL"), None);
+        registry.expire();
+        assert!(registry.quotes().is_empty());
+    }
+
+    #[test]
+    fn forgets_learned_assets_but_not_configured_ones() {
+        let mut registry = PriceRegistry::new();
+        registry.track([AssetKey::evm(
+            "ethereum",
+            "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+        )]);
+        registry.forget_after = Duration::ZERO;
+        std::thread::sleep(Duration::from_millis(5));
+        registry.expire();
+
+        assert_eq!(registry.refresh_request().keys.len(), DEFAULT_COINS.len());
+    }
+
+    #[test]
+    fn unresolved_assets_are_not_retracked() {
+        let mut registry = PriceRegistry::new();
+        registry.track([AssetKey::symbol("NOPE")]);
+        registry.record(PriceUpdate {
+            unresolved: vec![AssetKey::symbol("NOPE"), AssetKey::symbol("SOL")],
+            ..PriceUpdate::default()
+        });
+
+        assert!(registry.track([AssetKey::symbol("nope")]).is_empty());
+        assert_eq!(registry.refresh_request().keys.len(), DEFAULT_COINS.len());
+    }
+}
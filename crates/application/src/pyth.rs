@@ -0,0 +1,299 @@
+use crate::AppError;
+use crate::oracle::{PYTH_FEEDS, PriceSource, symbols};
+use crate::prices::AssetKey;
+use crate::solana::SolanaRpc;
+use async_trait::async_trait;
+use serde::Serialize;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Pyth push oracle, owner of the sponsored price feed accounts. Same
+/// program and addresses on mainnet and devnet.
+pub const PYTH_PUSH_ORACLE: Pubkey = pubkey!("pythWSnswVUd12oZpeFP8e9CVaEqJg25g1Vtc2biRsT");
+
+/// Shard the sponsored feeds are written to.
+const SPONSORED_SHARD: u16 = 0;
+
+/// Anchor discriminator, write authority, `Full` verification level.
+const PRICE_MESSAGE_OFFSET: usize = 8 + 32 + 1;
+
+/// One reading of a Pyth price account.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PythPrice {
+    pub price: i64,
+    /// Same units as `price`.
+    pub conf: u64,
+    pub expo: i32,
+    /// Unix seconds.
+    pub publish_time: i64,
+}
+
+impl PythPrice {
+    pub fn usd(&self) -> f64 {
+        self.price as f64 * 10f64.powi(self.expo)
+    }
+
+    pub fn confidence_usd(&self) -> f64 {
+        self.conf as f64 * 10f64.powi(self.expo)
+    }
+
+    /// Confidence interval as a fraction of the price.
+    pub fn confidence_ratio(&self) -> f64 {
+        self.conf as f64 / self.price.unsigned_abs() as f64
+    }
+
+    pub fn age(&self, now: SystemTime) -> Duration {
+        let now = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+        Duration::from_secs((now - self.publish_time).max(0) as u64)
+    }
+
+    /// Parses a `PriceUpdateV2` account of the Pyth receiver program and
+    /// checks it carries the expected feed.
+    pub fn from_account_data(data: &[u8], feed_id: &[u8; 32]) -> Result<Self, AppError> {
+        let invalid = |reason: &str| AppError::PythAccountErr(reason.to_string());
+
+        if data.get(..8) != Some(&price_update_discriminator()[..]) {
+            return Err(invalid("not a price update account"));
+        }
+        // Partial verification carries the number of signatures after the tag.
+        let message = match data.get(8 + 32) {
+            Some(1) => &data[PRICE_MESSAGE_OFFSET..],
+            Some(0) => return Err(invalid("price update is only partially verified")),
+            _ => return Err(invalid("unknown verification level")),
+        };
+        if message.len() < 32 + 8 + 8 + 4 + 8 {
+            return Err(invalid("account is too short"));
+        }
+        if &message[..32] != feed_id {
+            return Err(invalid("account holds another feed"));
+        }
+
+        let (price, rest) = message[32..].split_at(8);
+        let (conf, rest) = rest.split_at(8);
+        let (expo, rest) = rest.split_at(4);
+        Ok(PythPrice {
+            price: i64::from_le_bytes(price.try_into().unwrap()),
+            conf: u64::from_le_bytes(conf.try_into().unwrap()),
+            expo: i32::from_le_bytes(expo.try_into().unwrap()),
+            publish_time: i64::from_le_bytes(rest[..8].try_into().unwrap()),
+        })
+    }
+}
+
+fn price_update_discriminator() -> [u8; 8] {
+    let hash = solana_sdk::hash::hash(b"account:PriceUpdateV2");
+    hash.to_bytes()[..8].try_into().unwrap()
+}
+
+fn feed_id_bytes(hex: &str) -> Option<[u8; 32]> {
+    let hex = hex.trim_start_matches("0x");
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut bytes = [0u8; 32];
+    for (byte, pair) in bytes.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(bytes)
+}
+
+/// Address of the sponsored price feed account for a Pyth feed id.
+pub fn price_feed_account(feed_id: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(
+        &[&SPONSORED_SHARD.to_le_bytes(), feed_id],
+        &PYTH_PUSH_ORACLE,
+    )
+    .0
+}
+
+/// SOL, BTC and ETH prices read straight from their Pyth accounts.
+///
+/// A reading older than `max_age` fails with [`AppError::StalePrice`], one
+/// whose confidence interval is wider than `max_confidence` (a fraction of
+/// the price) with [`AppError::PriceConfidenceTooWide`].
+pub struct PythOnChainSource {
+    solana: Arc<SolanaRpc>,
+    feeds: Vec<(&'static str, [u8; 32])>,
+    pub max_age: Duration,
+    pub max_confidence: f64,
+}
+
+impl PythOnChainSource {
+    pub fn new(solana: Arc<SolanaRpc>) -> Self {
+        let feeds = PYTH_FEEDS
+            .iter()
+            .filter_map(|(symbol, id)| Some((*symbol, feed_id_bytes(id)?)))
+            .collect();
+        Self {
+            solana,
+            feeds,
+            max_age: Duration::from_secs(60),
+            max_confidence: 0.01,
+        }
+    }
+
+    /// Readings of the given symbols in one `getMultipleAccounts` call,
+    /// each checked on its own. Symbols without a feed are left out.
+    pub async fn read(
+        &self,
+        symbols: &[&str],
+    ) -> Result<HashMap<&'static str, Result<PythPrice, AppError>>, AppError> {
+        let feeds: Vec<&(&'static str, [u8; 32])> = self
+            .feeds
+            .iter()
+            .filter(|(symbol, _)| symbols.iter().any(|s| s.eq_ignore_ascii_case(symbol)))
+            .collect();
+        if feeds.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let accounts: Vec<Pubkey> = feeds.iter().map(|(_, id)| price_feed_account(id)).collect();
+        let accounts = self
+            .solana
+            .client()
+            .get_multiple_accounts(&accounts)
+            .await
+            .map_err(|_| AppError::SolanaRpcErr)?;
+
+        let now = SystemTime::now();
+        Ok(feeds
+            .into_iter()
+            .zip(accounts)
+            .map(|((symbol, feed_id), account)| {
+                let reading = account
+                    .ok_or_else(|| AppError::PythAccountErr("account not found".to_string()))
+                    .and_then(|account| PythPrice::from_account_data(&account.data, feed_id))
+                    .and_then(|price| self.check(symbol, price, now));
+                (*symbol, reading)
+            })
+            .collect())
+    }
+
+    pub fn check(
+        &self,
+        symbol: &str,
+        price: PythPrice,
+        now: SystemTime,
+    ) -> Result<PythPrice, AppError> {
+        let age = price.age(now);
+        if age > self.max_age {
+            return Err(AppError::StalePrice {
+                symbol: symbol.to_string(),
+                age,
+            });
+        }
+        let confidence = price.confidence_ratio();
+        if price.price <= 0 || confidence > self.max_confidence {
+            return Err(AppError::PriceConfidenceTooWide {
+                symbol: symbol.to_string(),
+                confidence,
+            });
+        }
+        Ok(price)
+    }
+}
+
+#[async_trait]
+impl PriceSource for PythOnChainSource {
+    fn name(&self) -> &'static str {
+        "pyth-onchain"
+    }
+
+    /// Stale and uncertain readings are left out, only RPC failures fail
+    /// the source.
+    async fn prices(&self, assets: &[AssetKey]) -> Result<HashMap<AssetKey, f64>, AppError> {
+        let symbols: Vec<&str> = symbols(assets).collect();
+        Ok(self
+            .read(&symbols)
+            .await?
+            .into_iter()
+            .filter_map(|(symbol, reading)| Some((AssetKey::symbol(symbol), reading.ok()?.usd())))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::{Cluster, SolanaConfig};
+
+    fn account(feed_id: &[u8; 32], price: i64, conf: u64, publish_time: i64) -> Vec<u8> {
+        let mut data = price_update_discriminator().to_vec();
+        data.extend([7u8; 32]);
+        data.push(1);
+        data.extend(feed_id);
+        data.extend(price.to_le_bytes());
+        data.extend(conf.to_le_bytes());
+        data.extend((-8i32).to_le_bytes());
+        data.extend(publish_time.to_le_bytes());
+        data.extend([0u8; 8 + 8 + 8 + 8]);
+        data
+    }
+
+    fn sol_feed() -> [u8; 32] {
+        feed_id_bytes(PYTH_FEEDS[0].1).unwrap()
+    }
+
+    #[test]
+    fn reads_price_update_accounts() {
+        let price = PythPrice::from_account_data(
+            &account(&sol_feed(), 18_050_000_000, 9_000_000, 1_700_000_000),
+            &sol_feed(),
+        )
+        .unwrap();
+
+        assert_eq!(price.usd(), 180.5);
+        assert_eq!(price.confidence_usd(), 0.09);
+        assert_eq!(price.publish_time, 1_700_000_000);
+    }
+
+    #[test]
+    fn rejects_other_accounts() {
+        let mut data = account(&sol_feed(), 1, 0, 0);
+        assert!(PythPrice::from_account_data(&data, &[0; 32]).is_err());
+        data[0] ^= 1;
+        assert!(matches!(
+            PythPrice::from_account_data(&data, &sol_feed()),
+            Err(AppError::PythAccountErr(_))
+        ));
+    }
+
+    #[test]
+    fn stale_and_uncertain_prices_are_errors() {
+        let source = PythOnChainSource::new(Arc::new(SolanaRpc::new(&SolanaConfig::new(
+            Cluster::Localnet,
+        ))));
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_100);
+        let price = |conf, publish_time| PythPrice {
+            price: 18_000_000_000,
+            conf,
+            expo: -8,
+            publish_time,
+        };
+
+        assert!(
+            source
+                .check("SOL", price(1_000_000, 1_700_000_090), now)
+                .is_ok()
+        );
+        assert!(matches!(
+            source.check("SOL", price(1_000_000, 1_700_000_000), now),
+            Err(AppError::StalePrice { age, .. }) if age == Duration::from_secs(100)
+        ));
+        assert!(matches!(
+            source.check("SOL", price(900_000_000, 1_700_000_090), now),
+            Err(AppError::PriceConfidenceTooWide { .. })
+        ));
+    }
+
+    #[test]
+    fn derives_sponsored_feed_accounts() {
+        assert_eq!(
+            price_feed_account(&sol_feed()).to_string(),
+            "7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE"
+        );
+    }
+}
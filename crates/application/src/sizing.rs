@@ -0,0 +1,374 @@
+use crate::AppError;
+use crate::hedge::Side;
+use crate::market::Venue;
+use crate::terms::{Direction, Settlement};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Added by Polymarket on top of the quoted share price.
+pub const POLYMARKET_FEE: f64 = 0.02;
+
+/// Kalshi charges `0.07 * P * (1 - P)` per contract.
+const KALSHI_FEE_RATE: f64 = 0.07;
+
+pub fn fee_per_share(venue: Venue, share_price: f64) -> f64 {
+    match venue {
+        Venue::Polymarket => POLYMARKET_FEE,
+        Venue::Kalshi => KALSHI_FEE_RATE * share_price * (1.0 - share_price),
+    }
+}
+
+/// A long spot position protected by a binary contract that pays `$1` per
+/// share when the underlying settles on the protected side of `strike`, see
+/// [`SizingInput::pays_at`].
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SizingInput {
+    pub position_usd: f64,
+    pub spot_price: f64,
+    pub strike: f64,
+    /// Share of the loss between spot and strike to cover, `0.0..=1.0`.
+    pub coverage: f64,
+    pub share_price: f64,
+    pub fee_per_share: f64,
+    /// What the contract resolves YES on.
+    pub direction: Direction,
+    pub settlement: Settlement,
+    /// The side that is bought.
+    pub side: Side,
+}
+
+impl SizingInput {
+    /// Whether the side bought of an at-expiry contract pays if the
+    /// underlying settles at `price`. YES of "below $X" needs the price under
+    /// `X`, NO of "above $X" pays on `X` too.
+    pub fn pays_at(&self, price: f64) -> bool {
+        let resolves_yes = match self.direction {
+            Direction::Above => price > self.strike,
+            Direction::Below => price < self.strike,
+        };
+        resolves_yes == (self.side == Side::Yes)
+    }
+
+    /// Whether a barrier contract was necessarily touched if the underlying
+    /// settles at `price`. Anywhere else it may or may not have been.
+    fn touched_at(&self, price: f64) -> bool {
+        match self.direction {
+            Direction::Above => price >= self.strike,
+            Direction::Below => price <= self.strike,
+        }
+    }
+
+    /// Whether the side bought pays, given where the underlying settles and,
+    /// for a barrier contract, whether the strike was touched on the way.
+    fn pays(&self, price: f64, touched: Option<bool>) -> bool {
+        match touched {
+            Some(touched) => touched == (self.side == Side::Yes),
+            None => self.pays_at(price),
+        }
+    }
+
+    /// Strike touched or not for each way the underlying can settle at
+    /// `price`, `None` alone for an at-expiry contract.
+    fn outcomes(&self, price: f64) -> &'static [Option<bool>] {
+        match self.settlement {
+            Settlement::AtExpiry => &[None],
+            Settlement::Barrier if self.touched_at(price) => &[Some(true)],
+            Settlement::Barrier => &[Some(true), Some(false)],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+pub struct HedgeSize {
+    pub shares: f64,
+    /// Share price including the fee.
+    pub effective_price: f64,
+    pub stake: f64,
+    pub max_payout: f64,
+    /// Spot loss if the underlying lands exactly on the strike.
+    pub loss_at_strike: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+pub struct ScenarioPoint {
+    pub terminal_price: f64,
+    /// Whether a barrier contract's strike was touched before expiry, `None`
+    /// for contracts that settle at expiry.
+    pub touched: Option<bool>,
+    pub spot_pnl: f64,
+    pub hedge_payout: f64,
+    pub hedge_pnl: f64,
+    pub net_pnl: f64,
+}
+
+pub fn size_hedge(input: &SizingInput) -> Result<HedgeSize, AppError> {
+    if input.position_usd <= 0.0 || input.spot_price <= 0.0 {
+        return Err(AppError::HedgeSizingErr(
+            "position must be positive".to_string(),
+        ));
+    }
+    if input.strike <= 0.0 || input.strike >= input.spot_price {
+        return Err(AppError::HedgeSizingErr(
+            "strike must be below the spot price".to_string(),
+        ));
+    }
+    if !(0.0..=1.0).contains(&input.coverage) {
+        return Err(AppError::HedgeSizingErr(
+            "coverage must be between 0 and 1".to_string(),
+        ));
+    }
+    let effective_price = input.share_price + input.fee_per_share;
+    if input.share_price <= 0.0 || effective_price >= 1.0 {
+        return Err(AppError::HedgeSizingErr(
+            "share price leaves no payout".to_string(),
+        ));
+    }
+
+    let loss_at_strike = input.position_usd * (input.spot_price - input.strike) / input.spot_price;
+    let shares = loss_at_strike * input.coverage;
+
+    Ok(HedgeSize {
+        shares,
+        effective_price,
+        stake: shares * effective_price,
+        max_payout: shares,
+        loss_at_strike,
+    })
+}
+
+/// Combined spot and hedge P&L if the underlying settles at each price. A
+/// barrier contract gets a touched and an untouched point at the prices it
+/// can settle at both ways.
+pub fn scenario_grid(input: &SizingInput, size: &HedgeSize, prices: &[f64]) -> Vec<ScenarioPoint> {
+    prices
+        .iter()
+        .flat_map(|&terminal_price| {
+            input.outcomes(terminal_price).iter().map(move |&touched| {
+                let spot_pnl = input.position_usd * (terminal_price / input.spot_price - 1.0);
+                let hedge_payout = if input.pays(terminal_price, touched) {
+                    size.max_payout
+                } else {
+                    0.0
+                };
+                let hedge_pnl = hedge_payout - size.stake;
+                ScenarioPoint {
+                    terminal_price,
+                    touched,
+                    spot_pnl,
+                    hedge_payout,
+                    hedge_pnl,
+                    net_pnl: spot_pnl + hedge_pnl,
+                }
+            })
+        })
+        .collect()
+}
+
+/// Terminal prices where the combined position neither gains nor loses.
+///
+/// Above the strike the spot gain has to pay back the stake. Below it the
+/// payout only offsets the loss down to the strike, so with `coverage <= 1`
+/// there is no second breakeven under the strike. A touched barrier pays
+/// wherever the price settles, so its breakeven can be above the strike.
+pub fn breakevens(input: &SizingInput, size: &HedgeSize) -> Vec<f64> {
+    let s = input.spot_price;
+    let p = input.position_usd;
+    let paid = s * (1.0 - (size.max_payout - size.stake) / p);
+    let unpaid = s * (1.0 + size.stake / p);
+
+    let (paid_possible, unpaid_possible) = match input.settlement {
+        Settlement::AtExpiry => (input.pays_at(paid), !input.pays_at(unpaid)),
+        Settlement::Barrier => (true, !input.touched_at(unpaid)),
+    };
+    [paid]
+        .into_iter()
+        .filter(|x| *x > 0.0 && paid_possible)
+        .chain([unpaid].into_iter().filter(|_| unpaid_possible))
+        .collect()
+}
+
+/// `steps + 1` evenly spaced prices from `low` to `high` inclusive.
+pub fn price_grid(low: f64, high: f64, steps: usize) -> Vec<f64> {
+    if steps == 0 {
+        return vec![low];
+    }
+    let step = (high - low) / steps as f64;
+    (0..=steps).map(|i| low + step * i as f64).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input() -> SizingInput {
+        SizingInput {
+            position_usd: 1_000.0,
+            spot_price: 200.0,
+            strike: 150.0,
+            coverage: 0.5,
+            share_price: 0.18,
+            fee_per_share: POLYMARKET_FEE,
+            direction: Direction::Below,
+            settlement: Settlement::AtExpiry,
+            side: Side::Yes,
+        }
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{a} != {b}");
+    }
+
+    #[test]
+    fn covers_requested_share_of_loss_at_strike() {
+        let size = size_hedge(&input()).unwrap();
+        assert_close(size.loss_at_strike, 250.0);
+        assert_close(size.shares, 125.0);
+        assert_close(size.max_payout, 125.0);
+    }
+
+    #[test]
+    fn stake_includes_fee() {
+        let size = size_hedge(&input()).unwrap();
+        assert_close(size.effective_price, 0.20);
+        assert_close(size.stake, 25.0);
+    }
+
+    #[test]
+    fn zero_coverage_stakes_nothing() {
+        let size = size_hedge(&SizingInput {
+            coverage: 0.0,
+            ..input()
+        })
+        .unwrap();
+        assert_close(size.shares, 0.0);
+        assert_close(size.stake, 0.0);
+    }
+
+    #[test]
+    fn rejects_strike_above_spot() {
+        assert!(
+            size_hedge(&SizingInput {
+                strike: 250.0,
+                ..input()
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn rejects_coverage_out_of_range() {
+        assert!(
+            size_hedge(&SizingInput {
+                coverage: 1.5,
+                ..input()
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn rejects_price_that_cannot_pay_out() {
+        assert!(
+            size_hedge(&SizingInput {
+                share_price: 0.99,
+                ..input()
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn yes_below_pays_only_under_the_strike() {
+        let input = input();
+        let size = size_hedge(&input).unwrap();
+        let grid = scenario_grid(&input, &size, &[100.0, 150.0, 200.0]);
+
+        assert_close(grid[0].spot_pnl, -500.0);
+        assert_close(grid[0].hedge_payout, 125.0);
+        assert_close(grid[0].net_pnl, -500.0 + 125.0 - 25.0);
+
+        assert_close(grid[1].hedge_payout, 0.0);
+        assert_close(grid[2].spot_pnl, 0.0);
+        assert_close(grid[2].net_pnl, -25.0);
+    }
+
+    #[test]
+    fn no_above_pays_on_the_strike() {
+        let input = SizingInput {
+            direction: Direction::Above,
+            side: Side::No,
+            ..input()
+        };
+        let size = size_hedge(&input).unwrap();
+        let grid = scenario_grid(&input, &size, &[100.0, 150.0, 150.000_001, 200.0]);
+
+        assert_close(grid[0].hedge_payout, 125.0);
+        assert_close(grid[1].hedge_payout, 125.0);
+        assert_close(grid[1].net_pnl, -250.0 + 125.0 - 25.0);
+        assert_close(grid[2].hedge_payout, 0.0);
+        assert_close(grid[3].net_pnl, -25.0);
+    }
+
+    #[test]
+    fn full_coverage_locks_loss_at_strike() {
+        let input = SizingInput {
+            coverage: 1.0,
+            ..input()
+        };
+        let size = size_hedge(&input).unwrap();
+        let just_below = scenario_grid(&input, &size, &[149.999_999])[0];
+        assert!((just_below.net_pnl + size.stake).abs() < 1e-3);
+    }
+
+    #[test]
+    fn barriers_pay_once_touched_wherever_the_price_settles() {
+        let input = SizingInput {
+            settlement: Settlement::Barrier,
+            ..input()
+        };
+        let size = size_hedge(&input).unwrap();
+        let grid = scenario_grid(&input, &size, &[100.0, 150.0, 200.0]);
+
+        let touched: Vec<Option<bool>> = grid.iter().map(|point| point.touched).collect();
+        assert_eq!(touched, [Some(true), Some(true), Some(true), Some(false)]);
+        assert_close(grid[1].hedge_payout, 125.0);
+        assert_close(grid[2].terminal_price, 200.0);
+        assert_close(grid[2].net_pnl, 125.0 - 25.0);
+        assert_close(grid[3].net_pnl, -25.0);
+
+        // Touched above the strike, then not touched at all.
+        let points = breakevens(&input, &size);
+        assert_eq!(points.len(), 2);
+        assert_close(points[0], 180.0);
+        assert_close(points[1], 205.0);
+        let grid = scenario_grid(&input, &size, &points);
+        assert_close(grid[0].net_pnl, 0.0);
+        assert_close(grid[3].net_pnl, 0.0);
+    }
+
+    #[test]
+    fn breakevens_zero_the_net_pnl() {
+        let input = input();
+        let size = size_hedge(&input).unwrap();
+        let points = breakevens(&input, &size);
+
+        assert_eq!(points.len(), 1);
+        assert_close(points[0], 205.0);
+        assert_close(scenario_grid(&input, &size, &points)[0].net_pnl, 0.0);
+    }
+
+    #[test]
+    fn price_grid_is_inclusive() {
+        assert_eq!(
+            price_grid(100.0, 200.0, 4),
+            vec![100.0, 125.0, 150.0, 175.0, 200.0]
+        );
+        assert_eq!(price_grid(100.0, 200.0, 0), vec![100.0]);
+    }
+
+    #[test]
+    fn kalshi_fee_depends_on_price() {
+        assert_close(fee_per_share(Venue::Kalshi, 0.5), 0.0175);
+        assert_close(fee_per_share(Venue::Polymarket, 0.5), POLYMARKET_FEE);
+    }
+}
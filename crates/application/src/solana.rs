@@ -0,0 +1,174 @@
+use crate::AppError;
+use reqwest::header::{HeaderName, HeaderValue};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::RpcClientConfig;
+use solana_rpc_client::http_sender::HttpSender;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+const RPC_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Where chain reads go.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Cluster {
+    #[default]
+    MainnetBeta,
+    Devnet,
+    Testnet,
+    /// `solana-test-validator` on its default port.
+    Localnet,
+    Custom(String),
+}
+
+impl Cluster {
+    pub fn url(&self) -> &str {
+        match self {
+            Cluster::MainnetBeta => "https://api.mainnet-beta.solana.com",
+            Cluster::Devnet => "https://api.devnet.solana.com",
+            Cluster::Testnet => "https://api.testnet.solana.com",
+            Cluster::Localnet => "http://127.0.0.1:8899",
+            Cluster::Custom(url) => url,
+        }
+    }
+}
+
+/// A cluster name (`mainnet-beta`, `devnet`, `testnet`, `localnet`) or an
+/// RPC URL.
+impl FromStr for Cluster {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.to_lowercase().as_str() {
+            "mainnet-beta" | "mainnet" => Ok(Cluster::MainnetBeta),
+            "devnet" => Ok(Cluster::Devnet),
+            "testnet" => Ok(Cluster::Testnet),
+            "localnet" | "localhost" => Ok(Cluster::Localnet),
+            url if url.starts_with("http://") || url.starts_with("https://") => {
+                Ok(Cluster::Custom(s.to_string()))
+            }
+            _ => Err(AppError::InvalidSolanaCluster(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Cluster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cluster::MainnetBeta => write!(f, "mainnet-beta"),
+            Cluster::Devnet => write!(f, "devnet"),
+            Cluster::Testnet => write!(f, "testnet"),
+            Cluster::Localnet => write!(f, "localnet"),
+            Cluster::Custom(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+/// Cluster plus the header paid RPC providers authenticate with.
+#[derive(Debug, Clone, Default)]
+pub struct SolanaConfig {
+    pub cluster: Cluster,
+    auth_header: Option<(HeaderName, HeaderValue)>,
+}
+
+impl SolanaConfig {
+    pub fn new(cluster: Cluster) -> Self {
+        Self {
+            cluster,
+            auth_header: None,
+        }
+    }
+
+    /// Sends `Name: value` with every request. The value is kept out of
+    /// `Debug` output.
+    pub fn with_auth_header(mut self, header: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::InvalidSolanaCluster("malformed RPC auth header".to_string());
+        let (name, value) = header.split_once(':').ok_or_else(invalid)?;
+        let name = HeaderName::from_str(name.trim()).map_err(|_| invalid())?;
+        let mut value = HeaderValue::from_str(value.trim()).map_err(|_| invalid())?;
+        value.set_sensitive(true);
+        self.auth_header = Some((name, value));
+        Ok(self)
+    }
+
+    fn sender(&self) -> HttpSender {
+        let mut headers = HttpSender::default_headers();
+        if let Some((name, value)) = &self.auth_header {
+            headers.insert(name.clone(), value.clone());
+        }
+        let client = reqwest::Client::builder()
+            .default_headers(headers)
+            .timeout(RPC_TIMEOUT)
+            .pool_idle_timeout(RPC_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        HttpSender::new_with_client(self.cluster.url(), client)
+    }
+}
+
+/// One connection pool to the configured cluster, shared by everything that
+/// reads the chain.
+pub struct SolanaRpc {
+    cluster: Cluster,
+    client: RpcClient,
+}
+
+impl Default for SolanaRpc {
+    fn default() -> Self {
+        Self::new(&SolanaConfig::default())
+    }
+}
+
+impl SolanaRpc {
+    pub fn new(config: &SolanaConfig) -> Self {
+        Self {
+            cluster: config.cluster.clone(),
+            client: RpcClient::new_sender(config.sender(), RpcClientConfig::default()),
+        }
+    }
+
+    pub fn cluster(&self) -> &Cluster {
+        &self.cluster
+    }
+
+    pub fn client(&self) -> &RpcClient {
+        &self.client
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_clusters() {
+        assert_eq!("mainnet".parse::<Cluster>().unwrap(), Cluster::MainnetBeta);
+        assert_eq!(" Devnet ".parse::<Cluster>().unwrap(), Cluster::Devnet);
+        assert_eq!(
+            "localnet".parse::<Cluster>().unwrap().url(),
+            "http://127.0.0.1:8899"
+        );
+        assert_eq!(
+            "https://rpc.example.com/v1".parse::<Cluster>().unwrap(),
+            Cluster::Custom("https://rpc.example.com/v1".to_string())
+        );
+        assert!(matches!(
+            "moon".parse::<Cluster>(),
+            Err(AppError::InvalidSolanaCluster(_))
+        ));
+    }
+
+    #[test]
+    fn hides_the_auth_header() {
+        let config = SolanaConfig::new(Cluster::Localnet)
+            .with_auth_header("x-api-key: secret")
+            .unwrap();
+        assert!(!format!("{:?}", config).contains("secret"));
+        assert!(
+            SolanaConfig::default()
+                .with_auth_header("no colon")
+                .is_err()
+        );
+    }
+}
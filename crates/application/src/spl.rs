@@ -0,0 +1,168 @@
+use crate::AppError;
+use crate::prices::AssetKey;
+use futures_util::future::try_join_all;
+use serde::Deserialize;
+use solana_account_decoder::UiAccountData;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_client::rpc_response::RpcKeyedAccount;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+
+pub const SPL_TOKEN_PROGRAM: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_PROGRAM: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// Mint authority option, supply, then decimals. Token-2022 mints start
+/// with the same layout, extensions come after.
+const MINT_DECIMALS_OFFSET: usize = 4 + 32 + 8;
+const MINT_LEN: usize = 82;
+
+/// Everything an owner holds of one mint, over all its token accounts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenBalance {
+    pub mint: String,
+    /// Raw amount, in units of `10^-decimals`.
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+impl TokenBalance {
+    pub fn ui_amount(&self) -> f64 {
+        self.amount as f64 / 10f64.powi(self.decimals as i32)
+    }
+
+    pub fn key(&self) -> AssetKey {
+        AssetKey::solana_mint(&self.mint)
+    }
+}
+
+/// Decimals of an SPL or Token-2022 mint account.
+pub fn mint_decimals(data: &[u8]) -> Option<u8> {
+    (data.len() >= MINT_LEN).then(|| data[MINT_DECIMALS_OFFSET])
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedTokenAccount {
+    info: TokenAccountInfo,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenAccountInfo {
+    mint: String,
+    token_amount: TokenAmount,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenAmount {
+    amount: String,
+    decimals: u8,
+}
+
+/// Mint, raw amount and the decimals the RPC node reported, `None` for
+/// accounts it did not parse.
+fn parse_token_account(account: &RpcKeyedAccount) -> Option<(String, u64, u8)> {
+    let UiAccountData::Json(parsed) = &account.account.data else {
+        return None;
+    };
+    let ParsedTokenAccount { info } = serde_json::from_value(parsed.parsed.clone()).ok()?;
+    let amount = info.token_amount.amount.parse().ok()?;
+    Some((info.mint, amount, info.token_amount.decimals))
+}
+
+/// Non-zero SPL and Token-2022 balances of `owner`, one per mint, both
+/// programs asked at once. Decimals are what the node reported, the mint
+/// accounts are read in one batch with the metadata, see
+/// [`crate::token_metadata::TokenMetadataResolver`].
+pub async fn token_balances(
+    client: &RpcClient,
+    owner: &Pubkey,
+) -> Result<Vec<TokenBalance>, AppError> {
+    let programs = [SPL_TOKEN_PROGRAM, TOKEN_2022_PROGRAM].map(|program| {
+        client.get_token_accounts_by_owner(owner, TokenAccountsFilter::ProgramId(program))
+    });
+    let accounts = try_join_all(programs)
+        .await
+        .map_err(|_| AppError::SolanaRpcErr)?;
+
+    let mut amounts: BTreeMap<String, (u64, u8)> = BTreeMap::new();
+    for (mint, amount, decimals) in accounts.iter().flatten().filter_map(parse_token_account) {
+        let entry = amounts.entry(mint).or_insert((0, decimals));
+        entry.0 = entry.0.saturating_add(amount);
+    }
+
+    Ok(amounts
+        .into_iter()
+        .filter(|(_, (amount, _))| *amount > 0)
+        .map(|(mint, (amount, decimals))| TokenBalance {
+            mint,
+            amount,
+            decimals,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_account_decoder::UiAccount;
+
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    use solana_account_decoder::parse_account_data::ParsedAccount;
+
+    fn token_account(mint: &str, amount: &str) -> RpcKeyedAccount {
+        RpcKeyedAccount {
+            pubkey: Pubkey::new_unique().to_string(),
+            account: UiAccount {
+                lamports: 2_039_280,
+                data: UiAccountData::Json(ParsedAccount {
+                    program: "spl-token".to_string(),
+                    parsed: serde_json::json!({
+                        "type": "account",
+                        "info": {
+                            "mint": mint,
+                            "owner": Pubkey::new_unique().to_string(),
+                            "state": "initialized",
+                            "tokenAmount": {
+                                "amount": amount,
+                                "decimals": 6,
+                                "uiAmount": 1.5,
+                                "uiAmountString": "1.5"
+                            }
+                        }
+                    }),
+                    space: 165,
+                }),
+                owner: SPL_TOKEN_PROGRAM.to_string(),
+                executable: false,
+                rent_epoch: 0,
+                space: Some(165),
+            },
+        }
+    }
+
+    #[test]
+    fn reads_parsed_token_accounts() {
+        let (mint, amount, decimals) =
+            parse_token_account(&token_account(USDC, "1500000")).unwrap();
+        assert_eq!(mint, USDC);
+        assert_eq!(amount, 1_500_000);
+        assert_eq!(decimals, 6);
+    }
+
+    #[test]
+    fn reads_mint_decimals() {
+        let mut data = vec![0u8; MINT_LEN];
+        data[MINT_DECIMALS_OFFSET] = 5;
+        assert_eq!(mint_decimals(&data), Some(5));
+        assert_eq!(mint_decimals(&data[..40]), None);
+
+        let bonk = TokenBalance {
+            mint: "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263".to_string(),
+            amount: 123_450_000,
+            decimals: 5,
+        };
+        assert_eq!(bonk.ui_amount(), 1234.5);
+    }
+}
@@ -0,0 +1,47 @@
+//! Plain HTTP server the API client tests point their base URL at.
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+pub struct StandIn {
+    listener: TcpListener,
+    pub url: String,
+}
+
+impl StandIn {
+    pub async fn bind() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        Self { listener, url }
+    }
+
+    /// Answers one request per `(status, body)`, in order. The handle
+    /// resolves to the request lines it was sent.
+    pub fn serve(self, responses: Vec<(u16, String)>) -> JoinHandle<Vec<String>> {
+        tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (mut stream, _) = self.listener.accept().await.unwrap();
+                let mut request = vec![0u8; 8192];
+                let read = stream.read(&mut request).await.unwrap();
+                let request = String::from_utf8_lossy(&request[..read]).to_string();
+                requests.push(request.lines().next().unwrap_or_default().to_string());
+                let response = format!(
+                    "HTTP/1.1 {status} Stand-in\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+            requests
+        })
+    }
+}
+
+/// Binds and answers every request with a 200 and the next body.
+pub async fn serve_json(bodies: Vec<String>) -> (String, JoinHandle<Vec<String>>) {
+    let stand_in = StandIn::bind().await;
+    let url = stand_in.url.clone();
+    let server = stand_in.serve(bodies.into_iter().map(|body| (200, body)).collect());
+    (url, server)
+}
@@ -0,0 +1,484 @@
+use crate::AppError;
+use crate::market::{Asset, Market, Venue};
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
+use serde::Serialize;
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Above,
+    Below,
+}
+
+/// How the contract settles against the strike.
+///
+/// `Barrier` pays once the price touches the strike at any time before the
+/// deadline ("reach", "dip to"). `AtExpiry` only looks at the price at the
+/// deadline ("above $4,000 on October 17").
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Settlement {
+    Barrier,
+    AtExpiry,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+/// Structured description of a crypto price contract.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MarketTerms {
+    pub asset: Asset,
+    pub strike: f64,
+    pub direction: Direction,
+    pub settlement: Settlement,
+    pub expiry: Option<DateTime<Utc>>,
+    pub confidence: Confidence,
+}
+
+const BARRIER_UP: &[&str] = &["reach", "reaches", "hit", "hits", "touch", "touches"];
+const BARRIER_DOWN: &[&str] = &["dip", "dips", "drop", "drops", "fall", "falls", "crash"];
+const ABOVE: &[&str] = &["above", "over", "higher", "greater", "exceed", "exceeds"];
+const BELOW: &[&str] = &["below", "under", "lower", "less"];
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+impl MarketTerms {
+    /// Parses the title first and falls back to the slug and the rules text
+    /// for whatever the title does not state. Every fallback lowers the
+    /// confidence. `end_date` is used when no deadline is written anywhere.
+    pub fn parse(
+        title: &str,
+        slug: &str,
+        rules: &str,
+        end_date: Option<DateTime<Utc>>,
+    ) -> Result<Self, AppError> {
+        let title = Text::new(title);
+        let slug = Text::new(&slug.replace('-', " "));
+        let rules = Text::new(rules);
+        let sources = [&title, &slug, &rules];
+        let mut fallbacks = 0;
+
+        let asset = first(&sources, &mut fallbacks, Text::asset)
+            .ok_or_else(|| AppError::MarketTermsErr("no known asset".to_string()))?;
+        let (direction, settlement) = first(&sources, &mut fallbacks, Text::direction)
+            .ok_or_else(|| AppError::MarketTermsErr("no price direction".to_string()))?;
+        let strike = first(&sources, &mut fallbacks, Text::strike)
+            .ok_or_else(|| AppError::MarketTermsErr("no strike price".to_string()))?;
+
+        let default_year = end_date.unwrap_or_else(Utc::now).year();
+        let expiry = match first(&sources, &mut fallbacks, |t| t.deadline(default_year)) {
+            Some(deadline) => Some(deadline),
+            None => {
+                fallbacks += 1;
+                end_date
+            }
+        };
+
+        let confidence = match fallbacks {
+            0 => Confidence::High,
+            1 => Confidence::Medium,
+            _ => Confidence::Low,
+        };
+
+        Ok(Self {
+            asset,
+            strike,
+            direction,
+            settlement,
+            expiry,
+            confidence,
+        })
+    }
+}
+
+impl TryFrom<&Market> for MarketTerms {
+    type Error = AppError;
+
+    fn try_from(market: &Market) -> Result<Self, Self::Error> {
+        // Kalshi tickers are not prose, only Polymarket slugs are worth reading.
+        let slug = match market.venue {
+            Venue::Polymarket => market.id.as_str(),
+            Venue::Kalshi => "",
+        };
+        Self::parse(&market.question, slug, &market.rules, market.end_date)
+    }
+}
+
+/// First source that yields a value, counting how many sources had to be skipped.
+fn first<T>(
+    sources: &[&Text],
+    fallbacks: &mut u32,
+    find: impl Fn(&Text) -> Option<T>,
+) -> Option<T> {
+    sources.iter().enumerate().find_map(|(i, text)| {
+        let found = find(text)?;
+        *fallbacks += i as u32;
+        Some(found)
+    })
+}
+
+/// Lowercased words with surrounding punctuation removed, `$` and inner `,`/`.` kept.
+struct Text {
+    words: Vec<String>,
+}
+
+impl Text {
+    fn new(text: &str) -> Self {
+        let words = text
+            .to_lowercase()
+            .split(|c: char| c.is_whitespace() || c == '/' || c == '(' || c == ')')
+            .map(|w| {
+                w.trim_matches(|c: char| !(c.is_ascii_alphanumeric() || c == '$'))
+                    .to_string()
+            })
+            .filter(|w| !w.is_empty())
+            .collect();
+        Self { words }
+    }
+
+    fn has(&self, candidates: &[&str]) -> Option<usize> {
+        self.words
+            .iter()
+            .position(|w| candidates.contains(&w.as_str()))
+    }
+
+    fn asset(&self) -> Option<Asset> {
+        self.words.iter().find_map(|w| match w.as_str() {
+            "ether" => Some(Asset::Eth),
+            w => Asset::from_str(w).ok(),
+        })
+    }
+
+    fn direction(&self) -> Option<(Direction, Settlement)> {
+        // "dip below" is still a barrier, so barrier words win over plain ones.
+        if self.has(BARRIER_DOWN).is_some() {
+            Some((Direction::Below, Settlement::Barrier))
+        } else if self.has(BARRIER_UP).is_some() {
+            Some((Direction::Above, Settlement::Barrier))
+        } else if self.has(BELOW).is_some() {
+            Some((Direction::Below, Settlement::AtExpiry))
+        } else if self.has(ABOVE).is_some() {
+            Some((Direction::Above, Settlement::AtExpiry))
+        } else {
+            None
+        }
+    }
+
+    /// A `$` amount anywhere, otherwise the first bare number after a
+    /// direction word ("reach 260", "above 110,000"), up to a month name so
+    /// the day of a date is not taken for one.
+    fn strike(&self) -> Option<f64> {
+        if let Some(amount) = self
+            .words
+            .iter()
+            .filter(|w| w.starts_with('$'))
+            .find_map(|w| amount(&w[1..]))
+        {
+            return Some(amount);
+        }
+
+        let keywords: Vec<&str> = [BARRIER_UP, BARRIER_DOWN, ABOVE, BELOW].concat();
+        let start = self.has(&keywords)?;
+        self.words[start + 1..]
+            .iter()
+            .take(3)
+            .take_while(|w| month(w).is_none())
+            .find_map(|w| bare_amount(w))
+    }
+
+    fn deadline(&self, default_year: i32) -> Option<DateTime<Utc>> {
+        self.words.iter().enumerate().find_map(|(i, word)| {
+            let rest = &self.words[i + 1..];
+            match word.as_str() {
+                "before" => parse_date(rest, default_year, true),
+                "by" | "on" | "in" | "at" | "end" | "of" => parse_date(rest, default_year, false),
+                _ => None,
+            }
+        })
+    }
+}
+
+/// Parses `260`, `110,000`, `4.5k`, `1m`.
+fn amount(word: &str) -> Option<f64> {
+    let (digits, multiplier) = match word.char_indices().last()? {
+        (i, 'k') => (&word[..i], 1_000.0),
+        (i, 'm') => (&word[..i], 1_000_000.0),
+        _ => (word, 1.0),
+    };
+    if digits.is_empty() || !digits.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let value: f64 = digits.replace(',', "").parse().ok()?;
+    Some(value * multiplier)
+}
+
+/// An amount written without `$`. Values that look like years are ignored
+/// since titles commonly mention them next to the strike.
+fn bare_amount(word: &str) -> Option<f64> {
+    let value = amount(word)?;
+    let looks_like_year = word.bytes().all(|b| b.is_ascii_digit()) && is_year(value);
+    (!looks_like_year).then_some(value)
+}
+
+fn is_year(value: f64) -> bool {
+    (2000.0..=2100.0).contains(&value) && value.fract() == 0.0
+}
+
+/// Full month names and their abbreviations: `december`, `dec`, `sept`.
+fn month(word: &str) -> Option<u32> {
+    if word.len() < 3 {
+        return None;
+    }
+    MONTHS
+        .iter()
+        .position(|m| m.starts_with(word) && (word.len() <= 4 || *m == word))
+        .map(|i| i as u32 + 1)
+}
+
+/// `exclusive` deadlines ("before 2026") end at the start of the named
+/// period, inclusive ones ("by December 31", "in 2025") at its end.
+fn parse_date(words: &[String], default_year: i32, exclusive: bool) -> Option<DateTime<Utc>> {
+    let mut words = words.iter().map(String::as_str).skip_while(|w| *w == "the");
+    let first = words.next()?;
+
+    if let Ok(year) = first.parse::<i32>()
+        && is_year(year as f64)
+    {
+        return if exclusive {
+            start_of(year, 1, 1)
+        } else {
+            end_of(year, 12, 31)
+        };
+    }
+
+    let month = month(first)?;
+    let next = words.next();
+    let day = next.and_then(|w| {
+        w.trim_end_matches(|c: char| c.is_ascii_alphabetic())
+            .parse::<u32>()
+            .ok()
+            .filter(|d| (1..=31).contains(d))
+    });
+    let year_word = if day.is_some() { words.next() } else { next };
+    let year = year_word
+        .and_then(|w| w.parse::<i32>().ok())
+        .filter(|y| is_year(*y as f64))
+        .unwrap_or(default_year);
+
+    match (day, exclusive) {
+        (Some(day), true) => start_of(year, month, day),
+        (Some(day), false) => end_of(year, month, day),
+        (None, true) => start_of(year, month, 1),
+        (None, false) => end_of(year, month, last_day_of_month(year, month)?),
+    }
+}
+
+fn start_of(year: i32, month: u32, day: u32) -> Option<DateTime<Utc>> {
+    let date = NaiveDate::from_ymd_opt(year, month, day)?;
+    Some(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?))
+}
+
+fn end_of(year: i32, month: u32, day: u32) -> Option<DateTime<Utc>> {
+    let date = NaiveDate::from_ymd_opt(year, month, day)?;
+    Some(Utc.from_utc_datetime(&date.and_hms_opt(23, 59, 59)?))
+}
+
+fn last_day_of_month(year: i32, month: u32) -> Option<u32> {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    Some(
+        NaiveDate::from_ymd_opt(next_year, next_month, 1)?
+            .pred_opt()?
+            .day(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().into()
+    }
+
+    struct Case {
+        title: &'static str,
+        slug: &'static str,
+        rules: &'static str,
+        end_date: &'static str,
+        asset: Asset,
+        strike: f64,
+        direction: Direction,
+        settlement: Settlement,
+        expiry: &'static str,
+        confidence: Confidence,
+    }
+
+    const CASES: &[Case] = &[
+        Case {
+            title: "Will Solana reach $260 before 2026?",
+            slug: "will-solana-reach-260-before-2026-327-264-879-598",
+            rules: "",
+            end_date: "2025-12-31T12:00:00Z",
+            asset: Asset::Sol,
+            strike: 260.0,
+            direction: Direction::Above,
+            settlement: Settlement::Barrier,
+            expiry: "2026-01-01T00:00:00Z",
+            confidence: Confidence::High,
+        },
+        Case {
+            title: "Will Bitcoin be above $110,000 on December 31?",
+            slug: "",
+            rules: "",
+            end_date: "2025-12-31T17:00:00Z",
+            asset: Asset::Btc,
+            strike: 110_000.0,
+            direction: Direction::Above,
+            settlement: Settlement::AtExpiry,
+            expiry: "2025-12-31T23:59:59Z",
+            confidence: Confidence::High,
+        },
+        Case {
+            title: "Will ETH dip to $2000 by December 31, 2025?",
+            slug: "",
+            rules: "",
+            end_date: "2025-12-31T12:00:00Z",
+            asset: Asset::Eth,
+            strike: 2000.0,
+            direction: Direction::Below,
+            settlement: Settlement::Barrier,
+            expiry: "2025-12-31T23:59:59Z",
+            confidence: Confidence::High,
+        },
+        Case {
+            title: "Will ETH hit 4.5k in 2025?",
+            slug: "",
+            rules: "",
+            end_date: "2025-12-31T12:00:00Z",
+            asset: Asset::Eth,
+            strike: 4500.0,
+            direction: Direction::Above,
+            settlement: Settlement::Barrier,
+            expiry: "2025-12-31T23:59:59Z",
+            confidence: Confidence::High,
+        },
+        Case {
+            title: "Will the price be below 90k?",
+            slug: "bitcoin-below-90k-by-june-30",
+            rules: "",
+            end_date: "2025-06-30T12:00:00Z",
+            asset: Asset::Btc,
+            strike: 90_000.0,
+            direction: Direction::Below,
+            settlement: Settlement::AtExpiry,
+            expiry: "2025-06-30T23:59:59Z",
+            confidence: Confidence::Low,
+        },
+        Case {
+            title: "Ethereum on Friday?",
+            slug: "",
+            rules: "Resolves Yes if ETH trades above $4,000 at noon ET.",
+            end_date: "2025-10-17T16:00:00Z",
+            asset: Asset::Eth,
+            strike: 4000.0,
+            direction: Direction::Above,
+            settlement: Settlement::AtExpiry,
+            expiry: "2025-10-17T16:00:00Z",
+            confidence: Confidence::Low,
+        },
+        Case {
+            title: "Will SOL be above $200 on Dec 31?",
+            slug: "",
+            rules: "",
+            end_date: "2025-12-31T12:00:00Z",
+            asset: Asset::Sol,
+            strike: 200.0,
+            direction: Direction::Above,
+            settlement: Settlement::AtExpiry,
+            expiry: "2025-12-31T23:59:59Z",
+            confidence: Confidence::High,
+        },
+        Case {
+            title: "Bitcoin above ___ on June 30?",
+            slug: "bitcoin-above-120k-on-june-30",
+            rules: "",
+            end_date: "2025-06-30T16:00:00Z",
+            asset: Asset::Btc,
+            strike: 120_000.0,
+            direction: Direction::Above,
+            settlement: Settlement::AtExpiry,
+            expiry: "2025-06-30T23:59:59Z",
+            confidence: Confidence::Medium,
+        },
+    ];
+
+    #[test]
+    fn parses_titles() {
+        for case in CASES {
+            let terms =
+                MarketTerms::parse(case.title, case.slug, case.rules, Some(at(case.end_date)))
+                    .unwrap_or_else(|err| panic!("{}: {err}", case.title));
+            assert_eq!(terms.asset, case.asset, "{}", case.title);
+            assert_eq!(terms.strike, case.strike, "{}", case.title);
+            assert_eq!(terms.direction, case.direction, "{}", case.title);
+            assert_eq!(terms.settlement, case.settlement, "{}", case.title);
+            assert_eq!(terms.expiry, Some(at(case.expiry)), "{}", case.title);
+            assert_eq!(terms.confidence, case.confidence, "{}", case.title);
+        }
+    }
+
+    #[test]
+    fn rejects_non_price_markets() {
+        assert!(MarketTerms::parse("Will the Lakers win?", "", "", None).is_err());
+        assert!(MarketTerms::parse("Will BTC dominance rise?", "", "", None).is_err());
+        assert!(MarketTerms::parse("Will SOL go up in 2025?", "", "", None).is_err());
+    }
+
+    #[test]
+    fn parses_amounts() {
+        assert_eq!(amount("260"), Some(260.0));
+        assert_eq!(amount("110,000"), Some(110_000.0));
+        assert_eq!(amount("4.5k"), Some(4500.0));
+        assert_eq!(amount("1m"), Some(1_000_000.0));
+        assert_eq!(amount("k"), None);
+        assert_eq!(amount("june"), None);
+        // Years only count as strikes when written as dollars.
+        assert_eq!(amount("2000"), Some(2000.0));
+        assert_eq!(bare_amount("2025"), None);
+        assert_eq!(bare_amount("2,050"), Some(2050.0));
+    }
+
+    #[test]
+    fn before_ends_at_the_start_of_the_period() {
+        let before = MarketTerms::parse("Will BTC reach $150k before 2026?", "", "", None).unwrap();
+        let by =
+            MarketTerms::parse("Will BTC reach $150k by December 31, 2025?", "", "", None).unwrap();
+        assert_eq!(before.expiry, Some(at("2026-01-01T00:00:00Z")));
+        assert_eq!(by.expiry, Some(at("2025-12-31T23:59:59Z")));
+        assert!(by.expiry < before.expiry);
+    }
+}
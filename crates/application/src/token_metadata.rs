@@ -0,0 +1,631 @@
+use crate::AppError;
+use crate::solana::SolanaRpc;
+use crate::spl::{TOKEN_2022_PROGRAM, mint_decimals};
+use futures_util::{StreamExt, stream};
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use solana_sdk::account::Account;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+pub const METAPLEX_METADATA_PROGRAM: Pubkey =
+    pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Mints that are looked up on chain without an answer still get these.
+const TOKEN_LIST: &str = include_str!("token_list.json");
+
+/// `Key::MetadataV1` of the Metaplex program.
+const METAPLEX_METADATA_V1: u8 = 4;
+/// Token-2022 accounts are padded to the token account size before the
+/// account type and the extensions.
+const TOKEN_2022_ACCOUNT_TYPE_OFFSET: usize = 165;
+const TOKEN_2022_MINT: u8 = 1;
+const TOKEN_METADATA_EXTENSION: u16 = 19;
+/// `getMultipleAccounts` limit.
+const MAX_ACCOUNTS: usize = 100;
+/// Off-chain metadata documents fetched at once.
+const IMAGE_FETCHES: usize = 8;
+/// Off-chain metadata documents larger than this are not read.
+const MAX_METADATA_BYTES: usize = 256 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TokenMetadata {
+    #[serde(alias = "address")]
+    pub mint: String,
+    pub symbol: String,
+    pub name: String,
+    pub decimals: u8,
+    #[serde(alias = "logoURI")]
+    pub logo_uri: Option<String>,
+}
+
+/// Name, symbol and metadata URI as written on chain.
+#[derive(Debug, Clone, PartialEq)]
+struct OnChainMetadata {
+    name: String,
+    symbol: String,
+    uri: String,
+}
+
+/// Address of the Metaplex metadata account of a mint.
+pub fn metadata_account(mint: &Pubkey) -> Pubkey {
+    let seeds: [&[u8]; 3] = [
+        b"metadata",
+        METAPLEX_METADATA_PROGRAM.as_ref(),
+        mint.as_ref(),
+    ];
+    Pubkey::find_program_address(&seeds, &METAPLEX_METADATA_PROGRAM).0
+}
+
+/// Borsh string, fixed size fields are padded with NULs.
+fn read_string(data: &[u8]) -> Option<(String, &[u8])> {
+    let len = u32::from_le_bytes(data.get(..4)?.try_into().ok()?) as usize;
+    let bytes = data.get(4..4 + len)?;
+    let value = String::from_utf8_lossy(bytes)
+        .trim_end_matches('\0')
+        .trim()
+        .to_string();
+    Some((value, &data[4 + len..]))
+}
+
+/// Name, symbol and URI, in this order, as both layouts store them.
+fn read_fields(data: &[u8]) -> Option<OnChainMetadata> {
+    let (name, rest) = read_string(data)?;
+    let (symbol, rest) = read_string(rest)?;
+    let (uri, _) = read_string(rest)?;
+    Some(OnChainMetadata { name, symbol, uri })
+}
+
+/// Metaplex `Metadata` account: key, update authority, mint, then the fields.
+fn parse_metaplex(data: &[u8]) -> Option<OnChainMetadata> {
+    if *data.first()? != METAPLEX_METADATA_V1 {
+        return None;
+    }
+    read_fields(data.get(1 + 32 + 32..)?)
+}
+
+/// `TokenMetadata` extension of a Token-2022 mint: update authority and
+/// mint, then the fields.
+fn parse_token_2022(data: &[u8]) -> Option<OnChainMetadata> {
+    if *data.get(TOKEN_2022_ACCOUNT_TYPE_OFFSET)? != TOKEN_2022_MINT {
+        return None;
+    }
+    let mut extensions = &data[TOKEN_2022_ACCOUNT_TYPE_OFFSET + 1..];
+    while extensions.len() >= 4 {
+        let kind = u16::from_le_bytes([extensions[0], extensions[1]]);
+        let len = u16::from_le_bytes([extensions[2], extensions[3]]) as usize;
+        let value = extensions.get(4..4 + len)?;
+        if kind == TOKEN_METADATA_EXTENSION {
+            return read_fields(value.get(32 + 32..)?);
+        }
+        extensions = &extensions[4 + len..];
+    }
+    None
+}
+
+#[derive(Debug, Deserialize)]
+struct OffChainMetadata {
+    image: Option<String>,
+}
+
+/// Metadata URIs are written by whoever minted the token, only `https` URLs
+/// on public hosts are fetched. Names are checked again once resolved, see
+/// [`PublicResolver`].
+fn is_public_https(url: &Url) -> bool {
+    if url.scheme() != "https" {
+        return false;
+    }
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    match host.trim_start_matches('[').trim_end_matches(']').parse() {
+        Ok(ip) => is_public_ip(ip),
+        Err(_) => {
+            let domain = host.trim_end_matches('.').to_ascii_lowercase();
+            domain != "localhost" && !domain.ends_with(".localhost")
+        }
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        // Shared address space, carrier-grade NAT.
+        || (a == 100 && (64..128).contains(&b))
+        || a == 0
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    if let Some(ip) = ip.to_ipv4_mapped() {
+        return is_public_ipv4(ip);
+    }
+    let first = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        // Unique local and link-local.
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80)
+}
+
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => is_public_ipv6(ip),
+    }
+}
+
+/// `image` of an off-chain metadata document. Every visitor's browser loads
+/// the logo, so it has to pass the same check as the metadata URI.
+fn logo_of(document: &[u8]) -> Option<String> {
+    let image = serde_json::from_slice::<OffChainMetadata>(document)
+        .ok()?
+        .image?;
+    let image = Url::parse(&image).ok().filter(is_public_https)?;
+    Some(image.to_string())
+}
+
+/// System resolver that leaves out the addresses that are not public, so a
+/// name pointing inward is never connected to.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public_ip(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Symbol, name, decimals and logo of Solana mints.
+///
+/// Token-2022 metadata extensions come first, then Metaplex metadata
+/// accounts, then the bundled token list. A logo the list does not have is
+/// read from the `image` of the JSON the on-chain URI points to.
+///
+/// Answers are cached, what the RPC node could not be asked about is not.
+/// That nothing is known of a mint is only remembered for `unknown_ttl`, it
+/// may get metadata later. Past `max_cached` mints the expired and then the
+/// oldest entries make room.
+pub struct TokenMetadataResolver {
+    solana: Arc<SolanaRpc>,
+    client: Client,
+    listed: HashMap<String, TokenMetadata>,
+    cache: Mutex<HashMap<String, Cached>>,
+    pub unknown_ttl: Duration,
+    pub max_cached: usize,
+}
+
+#[derive(Debug)]
+struct Cached {
+    /// `None` when nothing was found about the mint.
+    metadata: Option<TokenMetadata>,
+    since: Instant,
+}
+
+impl TokenMetadataResolver {
+    pub fn new(solana: Arc<SolanaRpc>) -> Self {
+        let listed: Vec<TokenMetadata> =
+            serde_json::from_str(TOKEN_LIST).expect("bundled token list is valid");
+        Self {
+            solana,
+            client: Client::builder()
+                .timeout(Duration::from_secs(5))
+                .dns_resolver(Arc::new(PublicResolver))
+                // Every hop has to pass the check the first URL did.
+                .redirect(Policy::custom(|attempt| {
+                    if attempt.previous().len() < 5 && is_public_https(attempt.url()) {
+                        attempt.follow()
+                    } else {
+                        attempt.stop()
+                    }
+                }))
+                .build()
+                .unwrap_or_default(),
+            listed: listed
+                .into_iter()
+                .map(|token| (token.mint.clone(), token))
+                .collect(),
+            cache: Mutex::new(HashMap::new()),
+            unknown_ttl: Duration::from_secs(60 * 60),
+            max_cached: 10_000,
+        }
+    }
+
+    /// Bundled token list entry of `mint`.
+    pub fn listed(&self, mint: &str) -> Option<&TokenMetadata> {
+        self.listed.get(mint)
+    }
+
+    /// Metadata of every mint something is known about, keyed by mint.
+    pub async fn resolve(&self, mints: &[String]) -> HashMap<String, TokenMetadata> {
+        let mut resolved = HashMap::new();
+        let mut missing = Vec::new();
+        {
+            let cache = self.cache.lock().unwrap();
+            for mint in mints {
+                match cache.get(mint) {
+                    Some(Cached {
+                        metadata: Some(metadata),
+                        ..
+                    }) => {
+                        resolved.insert(mint.clone(), metadata.clone());
+                    }
+                    Some(cached) if !self.expired(cached) => {}
+                    _ => missing.push(mint.as_str()),
+                }
+            }
+        }
+        if missing.is_empty() {
+            return resolved;
+        }
+
+        match self.fetch(&missing).await {
+            Ok(mut fetched) => {
+                let mut cache = self.cache.lock().unwrap();
+                for mint in missing {
+                    let metadata = fetched.remove(mint);
+                    if let Some(metadata) = &metadata {
+                        resolved.insert(mint.to_string(), metadata.clone());
+                    }
+                    self.remember(&mut cache, mint, metadata);
+                }
+            }
+            Err(_) => resolved.extend(
+                missing
+                    .into_iter()
+                    .filter_map(|mint| Some((mint.to_string(), self.listed(mint)?.clone()))),
+            ),
+        }
+        resolved
+    }
+
+    /// Unknown mints are asked about again after `unknown_ttl`.
+    fn expired(&self, cached: &Cached) -> bool {
+        cached.metadata.is_none() && cached.since.elapsed() >= self.unknown_ttl
+    }
+
+    fn remember(
+        &self,
+        cache: &mut HashMap<String, Cached>,
+        mint: &str,
+        metadata: Option<TokenMetadata>,
+    ) {
+        if cache.len() >= self.max_cached && !cache.contains_key(mint) {
+            cache.retain(|_, cached| !self.expired(cached));
+        }
+        while cache.len() >= self.max_cached.max(1) && !cache.contains_key(mint) {
+            let Some(oldest) = cache
+                .iter()
+                .min_by_key(|(_, cached)| cached.since)
+                .map(|(mint, _)| mint.clone())
+            else {
+                break;
+            };
+            cache.remove(&oldest);
+        }
+        cache.insert(
+            mint.to_string(),
+            Cached {
+                metadata,
+                since: Instant::now(),
+            },
+        );
+    }
+
+    /// Mint and Metaplex accounts of `mints` in as few calls as possible,
+    /// keyed by mint. Off-chain images are fetched a few at a time.
+    async fn fetch(&self, mints: &[&str]) -> Result<HashMap<String, TokenMetadata>, AppError> {
+        let rpc = self.solana.client();
+        let mut accounts_by_mint = Vec::new();
+        let mints: Vec<Pubkey> = mints
+            .iter()
+            .filter_map(|mint| Pubkey::from_str(mint).ok())
+            .collect();
+        for chunk in mints.chunks(MAX_ACCOUNTS / 2) {
+            let addresses: Vec<Pubkey> = chunk
+                .iter()
+                .copied()
+                .chain(chunk.iter().map(metadata_account))
+                .collect();
+            let mut mint_accounts = rpc
+                .get_multiple_accounts(&addresses)
+                .await
+                .map_err(|_| AppError::SolanaRpcErr)?;
+            let metadata_accounts = mint_accounts.split_off(chunk.len());
+            accounts_by_mint.extend(
+                chunk
+                    .iter()
+                    .map(Pubkey::to_string)
+                    .zip(mint_accounts)
+                    .zip(metadata_accounts),
+            );
+        }
+
+        Ok(stream::iter(accounts_by_mint)
+            .map(|((mint, mint_account), metadata_account)| async move {
+                self.combine(&mint, mint_account.as_ref(), metadata_account.as_ref())
+                    .await
+            })
+            .buffer_unordered(IMAGE_FETCHES)
+            .filter_map(|metadata| async move { metadata })
+            .map(|metadata| (metadata.mint.clone(), metadata))
+            .collect()
+            .await)
+    }
+
+    async fn combine(
+        &self,
+        mint: &str,
+        mint_account: Option<&Account>,
+        metadata_account: Option<&Account>,
+    ) -> Option<TokenMetadata> {
+        let on_chain = mint_account
+            .filter(|account| account.owner == TOKEN_2022_PROGRAM)
+            .and_then(|account| parse_token_2022(&account.data))
+            .or_else(|| metadata_account.and_then(|account| parse_metaplex(&account.data)))
+            .filter(|metadata| !metadata.symbol.is_empty());
+        let listed = self.listed(mint);
+        let decimals = mint_account
+            .and_then(|account| mint_decimals(&account.data))
+            .or(listed.map(|token| token.decimals))?;
+
+        let mut logo_uri = listed.and_then(|token| token.logo_uri.clone());
+        if let (None, Some(on_chain)) = (&logo_uri, &on_chain) {
+            logo_uri = self.image(&on_chain.uri).await;
+        }
+
+        match (on_chain, listed) {
+            (Some(on_chain), _) => Some(TokenMetadata {
+                mint: mint.to_string(),
+                symbol: on_chain.symbol,
+                name: on_chain.name,
+                decimals,
+                logo_uri,
+            }),
+            (None, Some(listed)) => Some(TokenMetadata {
+                decimals,
+                ..listed.clone()
+            }),
+            (None, None) => None,
+        }
+    }
+
+    async fn image(&self, uri: &str) -> Option<String> {
+        let url = Url::parse(uri).ok().filter(is_public_https)?;
+        let mut response = self.client.get(url).send().await.ok()?;
+        if !response.status().is_success()
+            || response
+                .content_length()
+                .is_some_and(|length| length > MAX_METADATA_BYTES as u64)
+        {
+            return None;
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await.ok()? {
+            body.extend_from_slice(&chunk);
+            if body.len() > MAX_METADATA_BYTES {
+                return None;
+            }
+        }
+        logo_of(&body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::{Cluster, SolanaConfig};
+
+    fn borsh(value: &str, padded: usize) -> Vec<u8> {
+        let mut bytes = value.as_bytes().to_vec();
+        bytes.resize(padded.max(value.len()), 0);
+        let mut data = (bytes.len() as u32).to_le_bytes().to_vec();
+        data.extend(bytes);
+        data
+    }
+
+    fn fields() -> Vec<u8> {
+        let mut data = borsh("Bonk", 32);
+        data.extend(borsh("BONK", 10));
+        data.extend(borsh("https://arweave.net/bonk.json", 200));
+        data
+    }
+
+    #[test]
+    fn reads_metaplex_metadata() {
+        let mut data = vec![METAPLEX_METADATA_V1];
+        data.extend([1u8; 64]);
+        data.extend(fields());
+        data.extend([0u8; 20]);
+
+        let metadata = parse_metaplex(&data).unwrap();
+        assert_eq!(metadata.name, "Bonk");
+        assert_eq!(metadata.symbol, "BONK");
+        assert_eq!(metadata.uri, "https://arweave.net/bonk.json");
+        data[0] = 0;
+        assert!(parse_metaplex(&data).is_none());
+    }
+
+    #[test]
+    fn reads_token_2022_metadata_extension() {
+        let mut data = vec![0u8; TOKEN_2022_ACCOUNT_TYPE_OFFSET];
+        data.push(TOKEN_2022_MINT);
+        // Metadata pointer first, it has to be skipped.
+        data.extend(18u16.to_le_bytes());
+        data.extend(64u16.to_le_bytes());
+        data.extend([2u8; 64]);
+        let mut value = vec![3u8; 64];
+        value.extend(fields());
+        value.extend(0u32.to_le_bytes());
+        data.extend(TOKEN_METADATA_EXTENSION.to_le_bytes());
+        data.extend((value.len() as u16).to_le_bytes());
+        data.extend(value);
+
+        assert_eq!(parse_token_2022(&data).unwrap().symbol, "BONK");
+        assert!(parse_token_2022(&data[..TOKEN_2022_ACCOUNT_TYPE_OFFSET]).is_none());
+    }
+
+    #[test]
+    fn bundles_a_token_list() {
+        let resolver = TokenMetadataResolver::new(Arc::new(SolanaRpc::new(&SolanaConfig::new(
+            Cluster::Localnet,
+        ))));
+        let usdc = resolver
+            .listed("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v")
+            .unwrap();
+        assert_eq!(usdc.symbol, "USDC");
+        assert_eq!(usdc.decimals, 6);
+        assert!(usdc.logo_uri.is_some());
+    }
+
+    #[test]
+    fn fetches_images_from_public_https_hosts_only() {
+        for (uri, allowed) in [
+            ("https://arweave.net/bonk.json", true),
+            ("https://8.8.8.8/meta.json", true),
+            ("http://arweave.net/bonk.json", false),
+            ("ftp://arweave.net/bonk.json", false),
+            ("https://localhost/meta.json", false),
+            ("https://metadata.localhost./meta.json", false),
+            ("https://127.0.0.1/meta.json", false),
+            ("https://2130706433/meta.json", false),
+            ("https://10.0.0.8/meta.json", false),
+            ("https://192.168.1.1/meta.json", false),
+            ("https://169.254.169.254/latest/meta-data", false),
+            ("https://100.64.0.1/meta.json", false),
+            ("https://0.0.0.0/meta.json", false),
+            ("https://[::1]/meta.json", false),
+            ("https://[fd00::1]/meta.json", false),
+            ("https://[fe80::1]/meta.json", false),
+            ("https://[::ffff:127.0.0.1]/meta.json", false),
+        ] {
+            assert_eq!(is_public_https(&Url::parse(uri).unwrap()), allowed, "{uri}");
+        }
+    }
+
+    #[test]
+    fn keeps_public_https_logos_only() {
+        let logo =
+            |image: &str| logo_of(serde_json::json!({ "image": image }).to_string().as_bytes());
+        assert_eq!(
+            logo("https://arweave.net/bonk.png").as_deref(),
+            Some("https://arweave.net/bonk.png")
+        );
+        assert_eq!(logo("http://arweave.net/bonk.png"), None);
+        assert_eq!(logo("data:image/svg+xml;base64,PHN2Zz4="), None);
+        assert_eq!(logo("https://10.0.0.8/bonk.png"), None);
+        assert_eq!(logo("bonk.png"), None);
+        assert_eq!(logo_of(b"{}"), None);
+        assert_eq!(logo_of(b"<html>"), None);
+    }
+
+    #[tokio::test]
+    async fn resolves_public_addresses_only() {
+        let resolve = |host: &'static str| async move {
+            PublicResolver
+                .resolve(host.parse().unwrap())
+                .await
+                .map(|addrs| addrs.count())
+        };
+        assert!(resolve("localhost").await.is_err());
+        assert!(resolve("127.0.0.1").await.is_err());
+        assert_eq!(resolve("8.8.8.8").await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn remembers_mints_nothing_is_known_about() {
+        // The client asks for the node version before its first call.
+        let (url, server) = crate::stand_in::serve_json(vec![
+            r#"{"jsonrpc": "2.0", "id": 1, "result": {"solana-core": "1.18.26", "feature-set": 1}}"#
+                .to_string(),
+            r#"{"jsonrpc": "2.0", "id": 2, "result": {"context": {"slot": 1}, "value": [null, null]}}"#
+                .to_string(),
+        ])
+        .await;
+        let resolver = TokenMetadataResolver::new(Arc::new(SolanaRpc::new(&SolanaConfig::new(
+            Cluster::Custom(url),
+        ))));
+        let mint = Pubkey::new_unique().to_string();
+
+        assert!(
+            resolver
+                .resolve(std::slice::from_ref(&mint))
+                .await
+                .is_empty()
+        );
+        assert_eq!(server.await.unwrap().len(), 2);
+        assert_eq!(resolver.cache.lock().unwrap()[&mint].metadata, None);
+        // Answered from the cache, the stand-in is gone.
+        assert!(
+            resolver
+                .resolve(std::slice::from_ref(&mint))
+                .await
+                .is_empty()
+        );
+    }
+
+    fn unknown(resolver: &TokenMetadataResolver, mints: &[&str]) {
+        let mut cache = resolver.cache.lock().unwrap();
+        for mint in mints {
+            resolver.remember(&mut cache, mint, None);
+        }
+    }
+
+    #[test]
+    fn forgets_unknown_mints_after_a_while() {
+        let mut resolver = TokenMetadataResolver::new(Arc::new(SolanaRpc::new(
+            &SolanaConfig::new(Cluster::Localnet),
+        )));
+        unknown(&resolver, &["a"]);
+        assert!(!resolver.expired(&resolver.cache.lock().unwrap()["a"]));
+
+        resolver.unknown_ttl = Duration::ZERO;
+        assert!(resolver.expired(&resolver.cache.lock().unwrap()["a"]));
+    }
+
+    #[test]
+    fn caps_the_cache() {
+        let mut resolver = TokenMetadataResolver::new(Arc::new(SolanaRpc::new(
+            &SolanaConfig::new(Cluster::Localnet),
+        )));
+        resolver.max_cached = 2;
+        unknown(&resolver, &["a", "b"]);
+        std::thread::sleep(Duration::from_millis(5));
+        unknown(&resolver, &["c"]);
+
+        let cache = resolver.cache.lock().unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains_key("c"));
+    }
+
+    #[test]
+    fn derives_metadata_accounts() {
+        let usdc = Pubkey::from_str("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v").unwrap();
+        assert_eq!(
+            metadata_account(&usdc).to_string(),
+            "5x38Kp4hvdomTCnCrAny4UtMUt5rQBdB6px2K1Ui45Wq"
+        );
+    }
+}
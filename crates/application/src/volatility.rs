@@ -0,0 +1,227 @@
+use crate::hedge::Side;
+use crate::history::PricePoint;
+use crate::market::{Asset, Market};
+use crate::terms::{Direction, MarketTerms, Settlement};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+/// Crypto trades around the clock, a year is a calendar year.
+const YEAR_MS: f64 = 365.25 * 24.0 * 60.0 * 60.0 * 1000.0;
+
+/// Annualized volatility of log returns between consecutive points.
+///
+/// Squared returns are divided by the time they span, so points do not need
+/// to be evenly spaced. `None` with fewer than two points.
+pub fn realized_volatility(points: &[PricePoint]) -> Option<f64> {
+    let (variance, years) = points
+        .windows(2)
+        .filter(|pair| pair[0].usd > 0.0 && pair[1].usd > 0.0 && pair[1].time > pair[0].time)
+        .fold((0.0, 0.0), |(variance, years), pair| {
+            let r = (pair[1].usd / pair[0].usd).ln();
+            (
+                variance + r * r,
+                years + (pair[1].time - pair[0].time) as f64 / YEAR_MS,
+            )
+        });
+    (years > 0.0).then(|| (variance / years).sqrt())
+}
+
+/// Standard normal CDF, Abramowitz and Stegun 7.1.26 (error below 1.5e-7).
+pub fn norm_cdf(x: f64) -> f64 {
+    let z = x.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * z);
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - poly * (-z * z).exp();
+    if x >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+/// Probability the price ends above (or below) `strike` after `years`,
+/// driftless lognormal with volatility `sigma`.
+pub fn terminal_probability(
+    spot: f64,
+    strike: f64,
+    sigma: f64,
+    years: f64,
+    direction: Direction,
+) -> f64 {
+    let spread = sigma * years.sqrt();
+    let above = if spread > 0.0 {
+        norm_cdf(((spot / strike).ln() - spread * spread / 2.0) / spread)
+    } else if spot > strike {
+        1.0
+    } else {
+        0.0
+    };
+    match direction {
+        Direction::Above => above,
+        Direction::Below => 1.0 - above,
+    }
+}
+
+/// Probability the price touches `strike` at any time within `years`, same
+/// model as [`terminal_probability`]. Already past the strike counts as hit.
+pub fn barrier_probability(
+    spot: f64,
+    strike: f64,
+    sigma: f64,
+    years: f64,
+    direction: Direction,
+) -> f64 {
+    // Distance to the barrier in log space, positive while not hit.
+    let distance = match direction {
+        Direction::Above => (strike / spot).ln(),
+        Direction::Below => (spot / strike).ln(),
+    };
+    if distance <= 0.0 {
+        return 1.0;
+    }
+    let spread = sigma * years.sqrt();
+    if spread <= 0.0 {
+        return 0.0;
+    }
+
+    // Drift of the log price towards the barrier.
+    let drift = match direction {
+        Direction::Above => -sigma * sigma / 2.0,
+        Direction::Below => sigma * sigma / 2.0,
+    } * years;
+    let reflected = (2.0 * drift * distance / (spread * spread)).exp();
+    (norm_cdf((drift - distance) / spread) + reflected * norm_cdf((-drift - distance) / spread))
+        .clamp(0.0, 1.0)
+}
+
+/// Model probability of the YES outcome, `None` for markets without expiry
+/// or already expired.
+pub fn yes_probability(
+    terms: &MarketTerms,
+    spot: f64,
+    sigma: f64,
+    now: DateTime<Utc>,
+) -> Option<f64> {
+    let expiry = terms.expiry?;
+    let years = (expiry - now).num_milliseconds() as f64 / YEAR_MS;
+    if years <= 0.0 || spot <= 0.0 || terms.strike <= 0.0 {
+        return None;
+    }
+    Some(match terms.settlement {
+        Settlement::Barrier => {
+            barrier_probability(spot, terms.strike, sigma, years, terms.direction)
+        }
+        Settlement::AtExpiry => {
+            terminal_probability(spot, terms.strike, sigma, years, terms.direction)
+        }
+    })
+}
+
+/// Market price of one outcome against the model probability of it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+pub struct Edge {
+    pub side: Side,
+    /// Share price, the probability the market implies.
+    pub implied: f64,
+    pub model: f64,
+    /// `model - implied`, positive when the side is cheap.
+    pub edge: f64,
+    /// Annualized volatility the model used.
+    pub volatility: f64,
+}
+
+/// Spot price and realized volatility per asset, what edges are computed from.
+#[derive(Debug, Clone, Default)]
+pub struct VolatilityModel {
+    inputs: HashMap<Asset, (f64, f64)>,
+}
+
+impl VolatilityModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, asset: Asset, spot: f64, volatility: f64) {
+        self.inputs.insert(asset, (spot, volatility));
+    }
+
+    pub fn volatility(&self, asset: Asset) -> Option<f64> {
+        self.inputs.get(&asset).map(|(_, volatility)| *volatility)
+    }
+
+    pub fn edge(&self, market: &Market, terms: &MarketTerms, side: Side) -> Option<Edge> {
+        let (spot, volatility) = *self.inputs.get(&terms.asset)?;
+        let yes = yes_probability(terms, spot, volatility, Utc::now())?;
+        let implied = market.outcome_price(side.outcome())?;
+        let model = match side {
+            Side::Yes => yes,
+            Side::No => 1.0 - yes,
+        };
+        Some(Edge {
+            side,
+            implied,
+            model,
+            edge: model - implied,
+            volatility,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn normal_cdf() {
+        assert!(close(norm_cdf(0.0), 0.5));
+        assert!(close(norm_cdf(1.96), 0.975));
+        assert!(close(norm_cdf(-1.0), 0.158655));
+    }
+
+    #[test]
+    fn volatility_of_alternating_returns() {
+        // +-1% every hour is 1% hourly, sqrt(8766) times that a year.
+        let points: Vec<PricePoint> = (0..100)
+            .map(|i| PricePoint {
+                time: i * 3_600_000,
+                usd: if i % 2 == 0 { 100.0 } else { 101.0 },
+            })
+            .collect();
+        let expected = (101.0f64 / 100.0).ln() * (365.25f64 * 24.0).sqrt();
+        assert!(close(realized_volatility(&points).unwrap(), expected));
+        assert!(realized_volatility(&points[..1]).is_none());
+    }
+
+    #[test]
+    fn terminal_and_barrier_probabilities() {
+        let above = terminal_probability(100.0, 120.0, 0.8, 0.25, Direction::Above);
+        let below = terminal_probability(100.0, 120.0, 0.8, 0.25, Direction::Below);
+        assert!(close(above + below, 1.0));
+
+        // N(d2) + (spot / strike) N(d1) for a martingale price.
+        let touch = barrier_probability(100.0, 120.0, 0.8, 0.25, Direction::Above);
+        assert!(close(above, 0.255975));
+        assert!(close(touch, 0.588518));
+
+        let dip = barrier_probability(100.0, 80.0, 0.8, 0.25, Direction::Below);
+        let finish_below = terminal_probability(100.0, 80.0, 0.8, 0.25, Direction::Below);
+        assert!(dip > finish_below);
+
+        assert_eq!(
+            barrier_probability(100.0, 90.0, 0.8, 0.25, Direction::Above),
+            1.0
+        );
+        assert_eq!(
+            barrier_probability(100.0, 120.0, 0.0, 0.25, Direction::Above),
+            0.0
+        );
+    }
+}
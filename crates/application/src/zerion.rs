@@ -0,0 +1,458 @@
+use crate::prices::AssetKey;
+use crate::{AppError, AssetsRow};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use utoipa::ToSchema;
+
+pub const ZERION_API_URL: &str = "https://api.zerion.io/v1";
+
+const ZERION_AUTH_HEADER: &str = "Basic emtfZGV2X2RhNzg4OWRmMjUwZTQ1ZTFhNzAwY2M3OTg1YjE2MTQ3Og==";
+
+/// Zerion pages are at most 100 positions, more than this many pages is a
+/// wallet we would not render anyway.
+const MAX_PAGES: usize = 10;
+
+/// Zerion chain ids and the CoinGecko platform their tokens are priced on.
+const PLATFORMS: &[(&str, &str)] = &[
+    ("ethereum", "ethereum"),
+    ("arbitrum", "arbitrum-one"),
+    ("optimism", "optimistic-ethereum"),
+    ("polygon", "polygon-pos"),
+    ("base", "base"),
+    ("binance-smart-chain", "binance-smart-chain"),
+    ("avalanche", "avalanche"),
+    ("xdai", "xdai"),
+    ("linea", "linea"),
+    ("zksync-era", "zksync"),
+];
+
+/// How a position is held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PositionType {
+    Wallet,
+    Deposited,
+    Staked,
+    LiquidityPool,
+    Locked,
+    /// Borrowed, a debt rather than a holding.
+    Loan,
+    Reward,
+    Other,
+}
+
+impl PositionType {
+    /// Zerion reports liquidity as a `deposit` into a pool.
+    fn from_zerion(position_type: &str, in_pool: bool) -> Self {
+        match position_type {
+            "wallet" => PositionType::Wallet,
+            "deposit" if in_pool => PositionType::LiquidityPool,
+            "deposit" => PositionType::Deposited,
+            "staked" => PositionType::Staked,
+            "locked" => PositionType::Locked,
+            "loan" => PositionType::Loan,
+            "reward" => PositionType::Reward,
+            _ => PositionType::Other,
+        }
+    }
+}
+
+impl fmt::Display for PositionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            PositionType::Wallet => "wallet",
+            PositionType::Deposited => "deposited",
+            PositionType::Staked => "staked",
+            PositionType::LiquidityPool => "liquidity pool",
+            PositionType::Locked => "locked",
+            PositionType::Loan => "loan",
+            PositionType::Reward => "reward",
+            PositionType::Other => "other",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// One token on one chain, held one way.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZerionPosition {
+    pub symbol: String,
+    pub name: String,
+    /// Zerion chain id, `ethereum`, `arbitrum`, `solana`...
+    pub chain: String,
+    /// Token contract or mint on `chain`, `None` for the native token.
+    pub address: Option<String>,
+    pub quantity: f64,
+    pub decimals: u8,
+    pub price: Option<f64>,
+    pub value: Option<f64>,
+    pub position_type: PositionType,
+    pub icon_url: Option<String>,
+}
+
+impl ZerionPosition {
+    pub fn key(&self) -> AssetKey {
+        match (&self.address, self.chain.as_str()) {
+            (Some(mint), "solana") => AssetKey::solana_mint(mint),
+            (Some(address), chain) => PLATFORMS.iter().find(|(id, _)| *id == chain).map_or_else(
+                || AssetKey::symbol(&self.symbol),
+                |(_, platform)| AssetKey::evm(platform, address),
+            ),
+            (None, _) => AssetKey::symbol(&self.symbol),
+        }
+    }
+}
+
+impl From<ZerionPosition> for AssetsRow {
+    fn from(position: ZerionPosition) -> Self {
+        Self {
+            key: Some(position.key()),
+            value: position
+                .value
+                .map_or("N/A".to_string(), |value| format!("{:.2}", value)),
+            balance: position.quantity.to_string(),
+            mint: position.address.filter(|_| position.chain == "solana"),
+            asset: position.symbol,
+            name: Some(position.name),
+            decimals: Some(position.decimals),
+            logo_uri: position.icon_url,
+            chain: Some(position.chain),
+            position_type: Some(position.position_type),
+            price: position.price,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PositionsResponse {
+    #[serde(default)]
+    links: Links,
+    #[serde(default)]
+    data: Vec<PositionData>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Links {
+    next: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PositionData {
+    attributes: PositionAttributes,
+    relationships: Relationships,
+}
+
+#[derive(Debug, Deserialize)]
+struct PositionAttributes {
+    position_type: String,
+    quantity: Quantity,
+    price: Option<f64>,
+    value: Option<f64>,
+    fungible_info: FungibleInfo,
+    pool_address: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Quantity {
+    float: f64,
+    decimals: u8,
+}
+
+#[derive(Debug, Deserialize)]
+struct FungibleInfo {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    symbol: String,
+    icon: Option<Icon>,
+    #[serde(default)]
+    implementations: Vec<Implementation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Icon {
+    url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Implementation {
+    chain_id: String,
+    address: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Relationships {
+    chain: Relationship,
+}
+
+#[derive(Debug, Deserialize)]
+struct Relationship {
+    data: RelationshipData,
+}
+
+#[derive(Debug, Deserialize)]
+struct RelationshipData {
+    id: String,
+}
+
+impl From<PositionData> for ZerionPosition {
+    fn from(data: PositionData) -> Self {
+        let PositionAttributes {
+            position_type,
+            quantity,
+            price,
+            value,
+            fungible_info,
+            pool_address,
+        } = data.attributes;
+        let chain = data.relationships.chain.data.id;
+        let address = fungible_info
+            .implementations
+            .into_iter()
+            .find(|implementation| implementation.chain_id == chain)
+            .and_then(|implementation| implementation.address);
+
+        Self {
+            symbol: fungible_info.symbol,
+            name: fungible_info.name,
+            address,
+            quantity: quantity.float,
+            decimals: quantity.decimals,
+            price,
+            value,
+            position_type: PositionType::from_zerion(&position_type, pool_address.is_some()),
+            icon_url: fungible_info.icon.and_then(|icon| icon.url),
+            chain,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ZerionClient {
+    client: Client,
+    base_url: String,
+}
+
+impl Default for ZerionClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ZerionClient {
+    pub fn new() -> Self {
+        Self::with_base_url(ZERION_API_URL)
+    }
+
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Every non-trash position of `address` on every chain Zerion indexes,
+    /// wallet balances and protocol positions alike, following `links.next`.
+    pub async fn positions(&self, address: &str) -> Result<Vec<ZerionPosition>, AppError> {
+        let mut request = self
+            .client
+            .get(format!("{}/wallets/{}/positions/", self.base_url, address))
+            .query(&[
+                ("filter[positions]", "no_filter"),
+                ("filter[trash]", "only_non_trash"),
+                ("currency", "usd"),
+                ("page[size]", "100"),
+            ]);
+
+        let mut positions = Vec::new();
+        for pages in 1.. {
+            let page: PositionsResponse = self.get_json(request).await?;
+            positions.extend(page.data.into_iter().map(ZerionPosition::from));
+            let Some(next) = page.links.next else { break };
+            if pages == MAX_PAGES {
+                eprintln!("Zerion positions of {address} cut off after {MAX_PAGES} pages");
+                break;
+            }
+            request = self.client.get(self.next_page(&next)?);
+        }
+        Ok(positions)
+    }
+
+    /// `links.next` resolved against the API, refusing any other origin so the
+    /// authorization header is only ever sent to Zerion.
+    fn next_page(&self, next: &str) -> Result<reqwest::Url, AppError> {
+        let base = reqwest::Url::parse(&self.base_url).map_err(|_| AppError::ZerionApiErr)?;
+        match base.join(next) {
+            Ok(url) if url.origin() == base.origin() => Ok(url),
+            _ => {
+                eprintln!("Zerion next page {next} is not on {}", self.base_url);
+                Err(AppError::ZerionApiErr)
+            }
+        }
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<T, AppError> {
+        let response = request
+            .header("accept", "application/json")
+            .header("authorization", ZERION_AUTH_HEADER)
+            .send()
+            .await
+            .map_err(|_| AppError::ZerionApiErr)?;
+
+        if !response.status().is_success() {
+            return Err(AppError::ZerionApiErr);
+        }
+
+        response
+            .json::<T>()
+            .await
+            .map_err(|_| AppError::ZerionApiErr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stand_in::StandIn;
+
+    fn position(
+        chain: &str,
+        symbol: &str,
+        address: Option<&str>,
+        position_type: &str,
+        pool_address: Option<&str>,
+    ) -> serde_json::Value {
+        serde_json::json!({
+            "type": "positions",
+            "id": format!("{symbol}-{chain}-{position_type}"),
+            "attributes": {
+                "parent": null,
+                "protocol": null,
+                "name": "Asset",
+                "position_type": position_type,
+                "quantity": {"int": "1500000", "decimals": 6, "float": 1.5, "numeric": "1.5"},
+                "value": 3.0,
+                "price": 2.0,
+                "fungible_info": {
+                    "name": format!("{symbol} token"),
+                    "symbol": symbol,
+                    "icon": {"url": format!("https://cdn.zerion.io/{symbol}.png")},
+                    "flags": {"verified": true},
+                    "implementations": [
+                        {"chain_id": "ethereum", "address": "0xA0B8", "decimals": 6},
+                        {"chain_id": chain, "address": address, "decimals": 6}
+                    ]
+                },
+                "flags": {"displayable": true, "is_trash": false},
+                "pool_address": pool_address
+            },
+            "relationships": {
+                "chain": {"data": {"type": "chains", "id": chain}},
+                "fungible": {"data": {"type": "fungibles", "id": symbol}}
+            }
+        })
+    }
+
+    fn page(data: Vec<serde_json::Value>, next: Option<String>) -> String {
+        serde_json::json!({"links": {"self": "", "next": next}, "data": data}).to_string()
+    }
+
+    #[test]
+    fn reads_positions_per_chain() {
+        let body = page(
+            vec![
+                position("arbitrum", "USDC", Some("0xAF88"), "wallet", None),
+                position("solana", "JUP", Some("JUPmint"), "staked", None),
+                position("base", "ETH", None, "deposit", Some("0xPool")),
+                position("polygon", "DAI", Some("0x8F3C"), "deposit", None),
+            ],
+            None,
+        );
+        let response: PositionsResponse = serde_json::from_str(&body).unwrap();
+        let positions: Vec<ZerionPosition> = response
+            .data
+            .into_iter()
+            .map(ZerionPosition::from)
+            .collect();
+
+        let usdc = &positions[0];
+        assert_eq!(usdc.chain, "arbitrum");
+        assert_eq!(usdc.address.as_deref(), Some("0xAF88"));
+        assert_eq!(usdc.quantity, 1.5);
+        assert_eq!(usdc.position_type, PositionType::Wallet);
+        assert_eq!(usdc.key(), AssetKey::evm("arbitrum-one", "0xAF88"));
+
+        assert_eq!(positions[1].position_type, PositionType::Staked);
+        assert_eq!(positions[1].key(), AssetKey::solana_mint("JUPmint"));
+        assert_eq!(positions[2].position_type, PositionType::LiquidityPool);
+        assert_eq!(positions[2].key(), AssetKey::symbol("ETH"));
+        assert_eq!(positions[3].position_type, PositionType::Deposited);
+
+        let row = AssetsRow::from(positions[1].clone());
+        assert_eq!(row.asset, "JUP");
+        assert_eq!(row.balance, "1.5");
+        assert_eq!(row.value, "3.00");
+        assert_eq!(row.mint.as_deref(), Some("JUPmint"));
+        assert_eq!(row.chain.as_deref(), Some("solana"));
+        assert_eq!(AssetsRow::from(positions[0].clone()).mint, None);
+    }
+
+    #[tokio::test]
+    async fn follows_next_links() {
+        let stand_in = StandIn::bind().await;
+        let base_url = stand_in.url.clone();
+        let next = format!("{base_url}/wallets/0xabc/positions/?page%5Bafter%5D=2");
+        let server = stand_in.serve(vec![
+            (
+                200,
+                page(
+                    vec![position("ethereum", "USDC", Some("0xA0B8"), "wallet", None)],
+                    Some(next),
+                ),
+            ),
+            (
+                200,
+                page(
+                    vec![position("optimism", "OP", Some("0x4200"), "reward", None)],
+                    None,
+                ),
+            ),
+        ]);
+
+        let positions = ZerionClient::with_base_url(&base_url)
+            .positions("0xabc")
+            .await
+            .unwrap();
+        let requests = server.await.unwrap();
+
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[1].position_type, PositionType::Reward);
+        assert!(requests[0].starts_with("GET /wallets/0xabc/positions/?"));
+        assert!(requests[0].contains("only_non_trash"));
+        assert!(requests[1].contains("page%5Bafter%5D=2 "));
+    }
+
+    #[tokio::test]
+    async fn refuses_next_links_to_other_origins() {
+        let stand_in = StandIn::bind().await;
+        let base_url = stand_in.url.clone();
+        let server = stand_in.serve(vec![(
+            200,
+            page(
+                vec![position("ethereum", "USDC", Some("0xA0B8"), "wallet", None)],
+                Some("http://127.0.0.1:9/wallets/0xabc/positions/?page%5Bafter%5D=2".into()),
+            ),
+        )]);
+
+        let result = ZerionClient::with_base_url(&base_url)
+            .positions("0xabc")
+            .await;
+
+        assert!(matches!(result, Err(AppError::ZerionApiErr)));
+        assert_eq!(server.await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,718 @@
+//! # API
+//! JSON-версія тих самих даних, що їх віддають htmx-ендпоінти, під `/api/v1`.
+//! Помилки завжди мають вигляд `{"error": {"code": "...", "message": "..."}}`,
+//! де `code` береться з `AppError::code`.
+//! ## OpenAPI
+//! Кожен обробник описаний `#[utoipa::path]`, а маршрути реєструються через
+//! `routes!`, тож шлях у специфікації і шлях у роутері - це один і той самий рядок.
+//! Специфікація віддається на `/api/openapi.json`.
+use crate::server::{HedgeScenarioRequest, LiveUpdate, ServerState};
+use application::catalog::CatalogEntry;
+use application::hedge::Side;
+use application::history::{Candle, MAX_HISTORY_DAYS, PricePoint, parse_interval};
+use application::volatility::realized_volatility;
+use application::{
+    AppError, Asset, AssetKey, AssetsRow, LamportBalance, Market, MarketTerms, PositionType,
+};
+use application::{Edge, Forecast, Forecaster, MarketCorrelation, VolatilityModel};
+use application::{HedgeEngine, HedgeScenario, HedgeSuggestion, Holding, WalletService};
+use application::{HistorySource, PriceHistory, PriceOracle, PriceQuote, PriceRegistry};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::RwLock;
+use tokio_stream::{Stream, StreamExt};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "HedgeYourFun", description = "Hedging spot crypto with prediction markets"),
+    tags(
+        (name = "htmx", description = "HTML fragments for the web UI"),
+        (name = "v1", description = "JSON API")
+    )
+)]
+pub struct ApiDoc;
+
+pub fn router() -> OpenApiRouter<ServerState> {
+    OpenApiRouter::new()
+        .routes(routes!(balance))
+        .routes(routes!(positions))
+        .routes(routes!(hedges))
+        .routes(routes!(prices))
+        .routes(routes!(ohlc))
+        .routes(routes!(forecast))
+        .routes(routes!(markets))
+        .routes(routes!(correlations))
+        .routes(routes!(scenario))
+        .routes(routes!(events))
+}
+
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<AppError> for ApiError {
+    fn from(err: AppError) -> Self {
+        let code = err.code();
+        let message = err.to_string();
+        Self {
+            status: err.into(),
+            code,
+            message,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ErrorBody {
+    error: ErrorDetails,
+}
+
+#[derive(Serialize, ToSchema)]
+struct ErrorDetails {
+    code: String,
+    message: String,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            error: ErrorDetails {
+                code: self.code.to_string(),
+                message: self.message,
+            },
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Balance {
+    pub address: String,
+    pub lamports: u64,
+    pub sol: f64,
+    pub usd: f64,
+    pub sol_to_usd: f64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/accounts/{address}/balance",
+    tag = "v1",
+    params(("address" = String, Path, description = "Solana wallet address")),
+    responses(
+        (status = 200, body = Balance),
+        (status = 400, body = ErrorBody),
+    )
+)]
+async fn balance(
+    State(ServerState {
+        solana,
+        price_registry,
+        ..
+    }): State<ServerState>,
+    Path(address): Path<String>,
+) -> ApiResult<Balance> {
+    let rate = price_registry.read().await.get_sol_to_usd();
+    let lamport_balance = LamportBalance::get(&solana, address.clone()).await?;
+
+    Ok(Json(Balance {
+        address,
+        lamports: lamport_balance.lamports(),
+        sol: lamport_balance.to_sol(),
+        usd: lamport_balance.to_usd(rate),
+        sol_to_usd: rate,
+    }))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Position {
+    pub asset: String,
+    /// Zerion chain id the position is on.
+    pub chain: Option<String>,
+    pub position_type: Option<PositionType>,
+    pub balance: f64,
+    /// `None` when we have no price for the asset.
+    pub value_usd: Option<f64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/accounts/{address}/positions",
+    tag = "v1",
+    params(("address" = String, Path, description = "Wallet address, EVM or Solana")),
+    responses(
+        (status = 200, body = Vec<Position>),
+        (status = 400, body = ErrorBody),
+    )
+)]
+async fn positions(
+    State(state): State<ServerState>,
+    Path(address): Path<String>,
+) -> ApiResult<Vec<Position>> {
+    let ServerState {
+        price_registry,
+        price_oracle,
+        ..
+    } = &state;
+    let wallet_assets = wallet_service(&state).get_wallet_assets(&address).await?;
+    learn_prices(price_registry, price_oracle, &wallet_assets).await;
+    let exchange_rates = price_registry.read().await;
+
+    let positions = wallet_assets
+        .into_iter()
+        .map(|asset| {
+            let balance: f64 = asset.balance.parse().unwrap_or(0.0);
+            Position {
+                value_usd: asset
+                    .usd_price(&exchange_rates)
+                    .map(|price| balance * price),
+                asset: asset.asset,
+                chain: asset.chain,
+                position_type: asset.position_type,
+                balance,
+            }
+        })
+        .collect();
+
+    Ok(Json(positions))
+}
+
+#[utoipa::path(
+    get,
+    path = "/prices",
+    tag = "v1",
+    responses((status = 200, body = Vec<PriceQuote>))
+)]
+async fn prices(
+    State(ServerState { price_registry, .. }): State<ServerState>,
+) -> ApiResult<Vec<PriceQuote>> {
+    Ok(Json(price_registry.read().await.quotes()))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OhlcQuery {
+    /// Candle width: `15m`, `1h`, `6h`, `1d`... `1h` when omitted.
+    pub interval: Option<String>,
+    /// How many days back, up to 90. 7 when omitted.
+    pub days: Option<u32>,
+}
+
+/// Candles from the server's own price snapshots. History the server has not
+/// seen yet is backfilled from CoinGecko on the first request. Only assets
+/// whose price is tracked have history.
+#[utoipa::path(
+    get,
+    path = "/prices/{asset}/ohlc",
+    tag = "v1",
+    params(
+        ("asset" = String, Path, description = "Ticker, `SOL`"),
+        OhlcQuery,
+    ),
+    responses(
+        (status = 200, body = Vec<Candle>),
+        (status = 400, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+    )
+)]
+async fn ohlc(
+    State(state): State<ServerState>,
+    Path(asset): Path<String>,
+    Query(OhlcQuery { interval, days }): Query<OhlcQuery>,
+) -> ApiResult<Vec<Candle>> {
+    let (candles, _) = candles(&state, &asset, interval.as_deref(), days).await?;
+    Ok(Json(candles))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ForecastQuery {
+    /// Candle width the forecast steps by, `6h` when omitted.
+    pub interval: Option<String>,
+    /// Days of history the forecast is fitted on, up to 90. 7 when omitted.
+    pub days: Option<u32>,
+    /// How many intervals ahead, up to 100. 12 when omitted.
+    pub steps: Option<usize>,
+}
+
+/// Rolling regression forecast with a prediction band and an EMA overlay,
+/// fitted on the candle closes of `/prices/{asset}/ohlc`.
+#[utoipa::path(
+    get,
+    path = "/prices/{asset}/forecast",
+    tag = "v1",
+    params(
+        ("asset" = String, Path, description = "Ticker, `SOL`"),
+        ForecastQuery,
+    ),
+    responses(
+        (status = 200, body = Forecast),
+        (status = 400, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+    )
+)]
+async fn forecast(
+    State(state): State<ServerState>,
+    Path(asset): Path<String>,
+    Query(ForecastQuery {
+        interval,
+        days,
+        steps,
+    }): Query<ForecastQuery>,
+) -> ApiResult<Forecast> {
+    let interval = interval.as_deref().unwrap_or(FORECAST_INTERVAL);
+    let (candles, width) = candles(&state, &asset, Some(interval), days).await?;
+    let steps = steps.unwrap_or(FORECAST_STEPS).clamp(1, 100);
+
+    Forecaster::new()
+        .forecast(&closes(&candles), width, steps)
+        .map(Json)
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::NOT_FOUND,
+                "no_price_history",
+                format!("Not enough price history for {}", asset),
+            )
+        })
+}
+
+/// What the chart draws: 6 hour candles over a week, 3 days ahead.
+const FORECAST_INTERVAL: &str = "6h";
+const FORECAST_DAYS: u32 = 7;
+const FORECAST_STEPS: usize = 12;
+
+/// Candles of `asset` for the last `days`, backfilled first if needed.
+/// Also returns the parsed interval.
+async fn candles(
+    ServerState {
+        price_registry,
+        price_history,
+        history_source,
+        ..
+    }: &ServerState,
+    asset: &str,
+    interval: Option<&str>,
+    days: Option<u32>,
+) -> Result<(Vec<Candle>, Duration), ApiError> {
+    let interval = interval.unwrap_or("1h");
+    let width = parse_interval(interval).ok_or_else(|| {
+        ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_interval",
+            format!("Invalid interval: {}", interval),
+        )
+    })?;
+    let days = days.unwrap_or(FORECAST_DAYS).clamp(1, MAX_HISTORY_DAYS);
+
+    let key = AssetKey::symbol(asset);
+    // Anything else would be searched for and backfilled for good.
+    if !price_registry.read().await.is_tracked(&key) {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            "unknown_asset",
+            format!("Prices of {} are not tracked", asset),
+        ));
+    }
+    // Without CoinGecko the snapshots taken so far are still worth serving.
+    let _ = load_history(price_history, history_source, &key, days).await;
+    let candles = price_history.read().await.ohlc(&key, width, days_ago(days));
+    if candles.is_empty() {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            "no_price_history",
+            format!("No price history for {}", asset),
+        ));
+    }
+
+    Ok((candles, width))
+}
+
+fn days_ago(days: u32) -> SystemTime {
+    SystemTime::now() - Duration::from_secs(days as u64 * 24 * 60 * 60)
+}
+
+fn closes(candles: &[Candle]) -> Vec<PricePoint> {
+    candles
+        .iter()
+        .map(|candle| PricePoint {
+            time: candle.time,
+            usd: candle.close,
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MarketsQuery {
+    /// `SOL`, `BTC` or `ETH`, all assets when omitted.
+    pub asset: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TrackedMarkets {
+    #[serde(serialize_with = "application::rfc3339")]
+    #[schema(value_type = String, format = DateTime)]
+    pub last_updated: std::time::SystemTime,
+    pub markets: Vec<TrackedMarket>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TrackedMarket {
+    #[serde(flatten)]
+    pub entry: CatalogEntry,
+    /// YES price against the model probability, `None` without enough
+    /// price history or expiry.
+    pub edge: Option<Edge>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/markets",
+    tag = "v1",
+    params(MarketsQuery),
+    responses(
+        (status = 200, body = TrackedMarkets),
+        (status = 400, body = ErrorBody),
+    )
+)]
+async fn markets(
+    State(state): State<ServerState>,
+    Query(MarketsQuery { asset }): Query<MarketsQuery>,
+) -> ApiResult<TrackedMarkets> {
+    let volatility = volatility_model(&state).await;
+    let catalog = state.market_catalog.read().await;
+    let entries = match asset {
+        Some(asset) => catalog.for_asset(parse_asset(&asset)?).to_vec(),
+        None => catalog.entries().cloned().collect(),
+    };
+    let markets = entries
+        .into_iter()
+        .map(|entry| TrackedMarket {
+            edge: volatility.edge(&entry.market, &entry.terms, Side::Yes),
+            entry,
+        })
+        .collect();
+
+    Ok(Json(TrackedMarkets {
+        last_updated: catalog.last_updated,
+        markets,
+    }))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CorrelationsQuery {
+    /// `SOL`, `BTC` or `ETH`, all assets when omitted.
+    pub asset: Option<String>,
+    /// Smallest absolute correlation to return, 0 when omitted.
+    pub min: Option<f64>,
+}
+
+/// How the YES price of every tracked market moved with spot over the last
+/// week of hourly snapshots, strongest first. Markets the catalog could not
+/// read terms from are included, a strong negative correlation makes one a
+/// hedge too.
+#[utoipa::path(
+    get,
+    path = "/correlations",
+    tag = "v1",
+    params(CorrelationsQuery),
+    responses(
+        (status = 200, body = Vec<MarketCorrelation>),
+        (status = 400, body = ErrorBody),
+    )
+)]
+async fn correlations(
+    State(ServerState {
+        price_history,
+        market_correlations,
+        ..
+    }): State<ServerState>,
+    Query(CorrelationsQuery { asset, min }): Query<CorrelationsQuery>,
+) -> ApiResult<Vec<MarketCorrelation>> {
+    let assets = match asset {
+        Some(asset) => vec![parse_asset(&asset)?],
+        None => Asset::ALL.to_vec(),
+    };
+    let min = min.unwrap_or(0.0).abs();
+
+    let tracker = market_correlations.read().await;
+    let history = price_history.read().await;
+    let since = SystemTime::now() - tracker.window;
+    let mut correlations: Vec<MarketCorrelation> = assets
+        .into_iter()
+        .flat_map(|asset| {
+            let key = AssetKey::symbol(asset.symbol());
+            let spot = closes(&history.ohlc(&key, tracker.interval, since));
+            tracker.correlations(asset, &spot)
+        })
+        .filter(|correlation| correlation.correlation.abs() >= min)
+        .collect();
+    correlations.sort_by(|a, b| b.correlation.abs().total_cmp(&a.correlation.abs()));
+
+    Ok(Json(correlations))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HedgesQuery {
+    /// Percentage of the loss down to the strike to protect.
+    pub coverage: Option<f64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/accounts/{address}/hedges",
+    tag = "v1",
+    params(
+        ("address" = String, Path, description = "Solana wallet address"),
+        HedgesQuery,
+    ),
+    responses(
+        (status = 200, body = Vec<HedgeSuggestion>),
+        (status = 400, body = ErrorBody),
+    )
+)]
+async fn hedges(
+    State(state): State<ServerState>,
+    Path(address): Path<String>,
+    Query(HedgesQuery { coverage }): Query<HedgesQuery>,
+) -> ApiResult<Vec<HedgeSuggestion>> {
+    Ok(Json(hedge_suggestions(&state, &address, coverage).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/hedge/scenario",
+    tag = "v1",
+    params(HedgeScenarioRequest),
+    responses(
+        (status = 200, body = HedgeScenario),
+        (status = 400, body = ErrorBody),
+        (status = 422, body = ErrorBody),
+        (status = 503, body = ErrorBody),
+    )
+)]
+async fn scenario(
+    State(state): State<ServerState>,
+    Query(request): Query<HedgeScenarioRequest>,
+) -> ApiResult<HedgeScenario> {
+    Ok(Json(hedge_scenario(&state, request).await?))
+}
+
+/// Same stream as `/events`, with the values as JSON: `prices` events
+/// carry the list of `PriceQuote`s, `odds` events the Polymarket SOL 260 odds.
+#[utoipa::path(
+    get,
+    path = "/events",
+    tag = "v1",
+    responses((status = 200, content_type = "text/event-stream", body = String))
+)]
+async fn events(
+    State(state): State<ServerState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = state.live_updates().await.filter_map(|update| {
+        let event = Event::default().event(update.event_name());
+        match update {
+            LiveUpdate::Prices(prices) => event.json_data(prices),
+            LiveUpdate::Odds(odds) => event.json_data(odds),
+        }
+        .ok()
+        .map(Ok)
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+fn parse_asset(asset: &str) -> Result<Asset, ApiError> {
+    asset.parse().map_err(|_| {
+        ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "unknown_asset",
+            format!("Unknown asset: {}", asset),
+        )
+    })
+}
+
+/// Wallet lookups share the server's RPC client and metadata cache.
+pub fn wallet_service(
+    ServerState {
+        solana,
+        token_metadata,
+        ..
+    }: &ServerState,
+) -> WalletService {
+    WalletService::with_services(Arc::clone(solana), Arc::clone(token_metadata))
+}
+
+/// Starts tracking what the wallet holds and prices the assets seen for the
+/// first time right away, so the response does not wait for the next refresh.
+pub async fn learn_prices(
+    price_registry: &RwLock<PriceRegistry>,
+    price_oracle: &PriceOracle,
+    assets: &[AssetsRow],
+) {
+    let request = price_registry
+        .write()
+        .await
+        .track(assets.iter().map(AssetsRow::price_key));
+    if request.is_empty() {
+        return;
+    }
+    if let Ok(update) = request.fetch(price_oracle).await {
+        price_registry.write().await.record(update);
+    }
+}
+
+/// Backfills the last `days` of `key` unless that was done already. The lock
+/// is not held while CoinGecko answers or while the file is written.
+pub async fn load_history(
+    price_history: &RwLock<PriceHistory>,
+    history_source: &HistorySource,
+    key: &AssetKey,
+    days: u32,
+) -> Result<(), AppError> {
+    if !price_history.read().await.needs_backfill(key, days) {
+        return Ok(());
+    }
+    let fetched = history_source.fetch(key, days).await?;
+    let appended = price_history.write().await.backfill(key, days, fetched);
+    appended.write().await
+}
+
+/// Days of hourly closes realized volatility is measured over.
+const VOLATILITY_DAYS: u32 = 30;
+
+/// Current spot and realized volatility of every hedgeable asset with
+/// enough history. Shared by the market and hedge endpoints.
+pub async fn volatility_model(
+    ServerState {
+        price_registry,
+        price_history,
+        ..
+    }: &ServerState,
+) -> VolatilityModel {
+    let prices = price_registry.read().await;
+    let history = price_history.read().await;
+    let mut model = VolatilityModel::new();
+    for asset in Asset::ALL {
+        let key = AssetKey::symbol(asset.symbol());
+        let candles = history.ohlc(
+            &key,
+            Duration::from_secs(60 * 60),
+            days_ago(VOLATILITY_DAYS),
+        );
+        if let (Some(spot), Some(volatility)) = (
+            prices.get_price(asset.symbol()),
+            realized_volatility(&closes(&candles)),
+        ) {
+            model.insert(asset, spot, volatility);
+        }
+    }
+    model
+}
+
+/// Shared by `/hedges` and `/api/v1/accounts/{address}/hedges`.
+pub async fn hedge_suggestions(
+    state: &ServerState,
+    account_id: &str,
+    coverage: Option<f64>,
+) -> Result<Vec<HedgeSuggestion>, AppError> {
+    let ServerState {
+        price_registry,
+        price_oracle,
+        price_history,
+        market_catalog,
+        ..
+    } = state;
+    let mut engine = HedgeEngine::new();
+    if let Some(coverage) = coverage {
+        engine.coverage = (coverage / 100.0).clamp(0.0, 1.0);
+    }
+
+    let wallet_assets = wallet_service(state).get_wallet_assets(account_id).await?;
+    learn_prices(price_registry, price_oracle, &wallet_assets).await;
+    let holdings = Holding::from_assets(&wallet_assets, &*price_registry.read().await);
+
+    // Only what is stored already, suggestions do not wait for a backfill.
+    let interval = parse_interval(FORECAST_INTERVAL).unwrap_or_default();
+    let history = price_history.read().await;
+    for holding in &holdings {
+        let key = AssetKey::symbol(holding.asset.symbol());
+        let candles = history.ohlc(&key, interval, days_ago(FORECAST_DAYS));
+        if let Some(band) = Forecaster::new()
+            .forecast(&closes(&candles), interval, FORECAST_STEPS)
+            .and_then(|forecast| forecast.band())
+        {
+            engine.forecast_bands.insert(holding.asset, band);
+        }
+    }
+    drop(history);
+    engine.volatility = volatility_model(state).await;
+
+    Ok(engine.suggest(&holdings, &*market_catalog.read().await))
+}
+
+/// Shared by `/hedge/scenario` and `/api/v1/hedge/scenario`.
+pub async fn hedge_scenario(
+    ServerState { price_registry, .. }: &ServerState,
+    HedgeScenarioRequest {
+        asset,
+        quantity,
+        venue,
+        market_id,
+        coverage,
+    }: HedgeScenarioRequest,
+) -> Result<HedgeScenario, ApiError> {
+    let asset = parse_asset(&asset)?;
+    let spot_price = price_registry
+        .read()
+        .await
+        .get_price(asset.symbol())
+        .filter(|price| *price > 0.0)
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "price_unavailable",
+                format!("No price for {} yet", asset),
+            )
+        })?;
+    let market = Market::fetch(venue, &market_id).await?;
+    let terms = MarketTerms::try_from(&market)?;
+
+    let holding = Holding {
+        asset,
+        quantity,
+        spot_price,
+    };
+    let coverage = coverage.map_or(1.0, |c| (c / 100.0).clamp(0.0, 1.0));
+    Ok(HedgeScenario::build(holding, market, terms, coverage)?)
+}
@@ -1,277 +1,804 @@
-//! # Application Server
-//! Веб серверний компоннент, для застосунку _HedgeYourFun_
-//! У обов'яки коптонетну входить:
-//! - обслуговування користувача
-//!   + надсилання сторінки, що ініцює взаємодіє з користувачем
-//!   + обробка запитів зі сторінки користувача
-//!   + частина реактивного інтерфейсу користувача
-//! - синхронізацізалія данних використовуючи підєднані api
-//! ## HTMX
-//! Застосунок покладається на [htmx](https://htmx.org/) для UI
-//! htmx-застосунки покладаються на сервер, що відпаравляє html
-//! Htmx буде вкладати відповідь у сторінку для досягнення SPA UX
-//! Наразі, застосунок відправляє як статичний (той, що не залежить від запиту користувача) html
-//! Так і динамічний (той, що залежить від запиту користувача)
-//! Побудова динамічних сторінок виконується шаблонним рушієм [askama](https://askama.readthedocs.io/en/stable/)
-//! Виклик шаблонів можна впізнати за `templates::`, що є початком виклику одного зі шаблонів у `crates/server/src/templates.rs`
-//! Виклик статичного html можна впізнати за `fs::read_to_string()`.
-//! ## Tokio
-//! `fs::read_to_string()` є скороченням від `tokio::fs::read_to_string`
-//! `tokio` - асинхронний рантайм для `rust`
-//! Ця програма асинхронна, `tokio` містить асинхронні api для стандартних функцій
-//! Тому якщо у вас колись буде вибір між використанням `std::XXX` та `tokio::XXX`
-//! у більшості випадків варто надати перевагу останньому.
-//! ## Axum
-//! `axum` це бекенд фреймворк.
-//! Для озайомлення з його можливосятями відівідайте [https://github.com/tokio-rs/axum/tree/main/examples](https://github.com/tokio-rs/axum/tree/main/examples)
-//! Оберіть бажану фічу та ознайомтеся з імлементацією
-//! ## Cookbook
-//! Іструкції для додавння функціоналу до цієї програми
-//! ### Ви хочете додати новий шаблон та ендпоінт для нього
-//! Для створення шаблону вам варто:
-//! - створити `html` файл у `crates/server/templates/`
-//! - створити `struct` (об'єкт) у `crates/server/src/templates.rs`
-//! - ендпоінт, що буде відповідати на запит
-//! - якщо, ви використовуєте html форму для отримання вводу користувача - поперньо створіть `struct` що відображає зміст цієї форми
-//! ___
-//! Ми хочемо взяти у користувача слово (`word`) та кількість (`number`), ми повернемо користувачу `ul>li{${rev_word}}*${number}`, де `rev_word` це слово задом-наперед.
-//! Спершу варто всяти у користувача ввід. Додаймо `html` форму до нашого `index.html`:
-//! ```html
-//! <form hx-post="/word_number">
-//!   <input name="word" type="text">
-//!   <input name="number" type="number" min="0" step="1">
-//!   <button type="submit">Submit</button>
-//! </form>
-//! ```
-//! Тепер варто додати `struct` до `server.rs`, що відображає форму
-//!
-//! ```
-//! #[derive(Debug, Deserialize)]
-//! pub struct WordNumber {
-//!     pub word: String,
-//!     pub number: u32,
-//! }
-//! ```
-//! При визначенні форми ми вказали намір роботи `POST` запити на `/word_number`
-//! Додаймо цей ендпоінт до нашого роутера
-//! ```
-//! let app = Router::new()
-//!     .route("/", get(index))
-//!     .route("/word_number", post(word_number)) // <== наш новий ендпоінт
-//!     .with_state(server_state);
-//! ```
-//! Перед тим як створити функцію `word_number`, створімо `html` шаблон, у `templates`
-//! нехай це буде `templates/word_number.html`
-//! ```jinja
-//! <ul>
-//!   {% for _ in range(end=number) %}
-//!     <li>{{ rev_word }}</li>
-//!   {% endfor %}
-//! </ul>
-//! ```
-//! Додамо обробник шаблона до файлу `src/templates.rs`,
-//! це `struct` який відоражає ввід для нашого шаблона,
-//! ```
-//! #[derive(Template)]
-//! #[template(path = "word_number.html")]
-//! pub struct WordNumber {
-//!     pub rev_word: String,
-//!     pub number: u32,
-//! }
-//! ```
-//! `<'a>` поруч з `WordNumber` та `rev_word` означає, що `rev_word` мусить померти (бути стертим з пам'яті) по смерті `WordNumber`
-//! Додамо обробника ендпоінта, раніше ми вказали, що цим буде займатися `word_number`
-//! ```
-//! async fn word_number(Form(WordNumber { word, number })) -> Result<Html<String>,StatusCode> {
-//!     let rev_word: String = word.chars().rev().collect();
-//!     let word_number = templates::WordNumber { rev_word, number };
-//!     let html = word_number
-//!         .render()
-//!         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-//!
-//!     Ok(Html(html))
-//! }
-//! ```
-mod server;
-mod templates;
-
-use crate::server::{ActivePolymarketSearch, LoadAccount, ServerState};
-use application::{AssetsRow, WalletService};
-use application::{ExchangePrices, LamportBalance, PolymarketSolana260};
-
-use askama::Template;
-use axum::{
-    Form, Router,
-    extract::State,
-    http::StatusCode,
-    response::Html,
-    routing::{get, post},
-};
-use std::{sync::Arc, time::Duration};
-use tokio::{fs, sync::RwLock};
-use tower_http::services::ServeDir;
-
-/// ## main
-/// Це роутер, тут визначаються енд поінти які може обробити сервер.
-/// ### route
-/// Для додавання нового ендпоінта
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let server_state = server_state_updater();
-
-    let app = Router::new()
-        .route("/", get(index))
-        .route("/account", post(account))
-        .route("/positions", post(positions))
-        .route("/favicon.ico", get(favicon))
-        .route("/calculator", get(calculator_body))
-        .route("/calculator", post(calc))
-        .nest_service("/css", ServeDir::new("crates/server/static/css"))
-        .nest_service("/js", ServeDir::new("crates/server/static/js"))
-        .nest_service("/images", ServeDir::new("crates/server/static/images"))
-        .nest_service("/effects", ServeDir::new("crates/server/static/effects"))
-        .nest_service("/static", ServeDir::new("crates/server/static"))
-        .with_state(server_state);
-
-    let port = std::env::var("PORT").unwrap_or_else(|_| "8081".to_string());
-    let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{port}")).await?;
-    println!("Running on http://0.0.0.0:{port}");
-    axum::serve(listener, app).await?;
-    Ok(())
-}
-
-async fn calculator_body() -> Result<Html<String>, StatusCode> {
-    match tokio::fs::read_to_string("crates/server/templates/calculator.html").await {
-        Ok(html) => Ok(Html(html)),
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
-    }
-}
-
-async fn calc(
-    State(ServerState {
-        exchange_prices: _,
-        polymarket_solana260,
-    }): State<ServerState>,
-    Form(ActivePolymarketSearch { money }): Form<ActivePolymarketSearch>,
-) -> Result<Html<String>, StatusCode> {
-    let polymarket_solana260_multiplyer = polymarket_solana260.read().await.answer_no_multiplier;
-    let bet_return = money / polymarket_solana260_multiplyer;
-    let html = format!("{bet_return:.2}$");
-    Ok(Html(html))
-}
-
-async fn account(
-    State(ServerState {
-        exchange_prices,
-        polymarket_solana260: _,
-    }): State<ServerState>,
-    Form(LoadAccount { account_id }): Form<LoadAccount>,
-) -> Result<Html<String>, StatusCode> {
-    let rate = exchange_prices.read().await.sol_to_usd;
-    let lamport_balance = LamportBalance::get(account_id)
-        .await
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
-    let sol = &format!("{:.2}", lamport_balance.to_sol());
-    let usd = &format!("{:.2}", lamport_balance.to_usd(rate));
-    let rate = &format!("{:.2}", exchange_prices.read().await.sol_to_usd);
-
-    let exchange_prices = templates::ExchangeRate { sol, usd, rate };
-    let html = exchange_prices
-        .render()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    Ok(Html(html))
-}
-
-async fn positions(
-    State(ServerState {
-        exchange_prices,
-        polymarket_solana260: _,
-    }): State<ServerState>,
-    Form(LoadAccount { account_id }): Form<LoadAccount>,
-) -> Result<Html<String>, StatusCode> {
-    let wallet_service = WalletService::new();
-    let exchange_rates = exchange_prices.read().await;
-
-    let wallet_assets = wallet_service
-        .get_wallet_assets(&account_id)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let assets_rows: Vec<AssetsRow> = wallet_assets
-        .into_iter()
-        .map(|asset| {
-            let usd_value = if let Some(price) = exchange_rates.get_price(&asset.asset) {
-                let balance: f64 = asset.balance.parse().unwrap_or(0.0); // Use asset.balance instead of asset.amount
-                format!("${:.2}", balance * price)
-            } else {
-                "N/A".to_string()
-            };
-
-            AssetsRow {
-                asset: asset.asset,
-                balance: asset.balance,
-                value: usd_value,
-            }
-        })
-        .collect();
-
-    let html = templates::AccountAssets { assets_rows }
-        .render()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    Ok(Html(html))
-}
-
-async fn index() -> Result<Html<String>, StatusCode> {
-    let index = fs::read_to_string("crates/server/templates/index.html")
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    Ok(Html(index))
-}
-
-async fn favicon() -> axum::response::Redirect {
-    axum::response::Redirect::permanent("/static/svg/icon.svg")
-}
-
-fn server_state_updater() -> ServerState {
-    let exchange_prices = Arc::new(RwLock::new(ExchangePrices::new()));
-    let polymarket_solana260 = Arc::new(RwLock::new(PolymarketSolana260::new()));
-
-    let exchange_prices_clone = Arc::clone(&exchange_prices);
-    let polymarket_solana260_clone = Arc::clone(&polymarket_solana260);
-
-    tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_secs(30));
-        loop {
-            interval.tick().await;
-            match ExchangePrices::get_sol_price().await {
-                Ok(new_exchange_prices) => {
-                    let mut guard = exchange_prices_clone.write().await;
-                    guard.last_updated = std::time::SystemTime::now();
-                    guard.sol_to_usd = new_exchange_prices;
-                }
-                _ => continue,
-            }
-        }
-    });
-    tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_secs(300));
-        loop {
-            interval.tick().await;
-            match PolymarketSolana260::update().await {
-                Ok(new_polymarket_solana260) => {
-                    let mut guard = polymarket_solana260_clone.write().await;
-                    guard.last_updated = std::time::SystemTime::now();
-                    guard.answer_no_multiplier = new_polymarket_solana260;
-                }
-                _ => continue,
-            }
-        }
-    });
-
-    ServerState {
-        exchange_prices,
-        polymarket_solana260,
-    }
-}
+//! # Application Server
+//! Веб серверний компоннент, для застосунку _HedgeYourFun_
+//! У обов'яки коптонетну входить:
+//! - обслуговування користувача
+//!   + надсилання сторінки, що ініцює взаємодіє з користувачем
+//!   + обробка запитів зі сторінки користувача
+//!   + частина реактивного інтерфейсу користувача
+//! - синхронізацізалія данних використовуючи підєднані api
+//! ## HTMX
+//! Застосунок покладається на [htmx](https://htmx.org/) для UI
+//! htmx-застосунки покладаються на сервер, що відпаравляє html
+//! Htmx буде вкладати відповідь у сторінку для досягнення SPA UX
+//! Наразі, застосунок відправляє як статичний (той, що не залежить від запиту користувача) html
+//! Так і динамічний (той, що залежить від запиту користувача)
+//! Побудова динамічних сторінок виконується шаблонним рушієм [askama](https://askama.readthedocs.io/en/stable/)
+//! Виклик шаблонів можна впізнати за `templates::`, що є початком виклику одного зі шаблонів у `crates/server/src/templates.rs`
+//! Виклик статичного html можна впізнати за `fs::read_to_string()`.
+//! ## Tokio
+//! `fs::read_to_string()` є скороченням від `tokio::fs::read_to_string`
+//! `tokio` - асинхронний рантайм для `rust`
+//! Ця програма асинхронна, `tokio` містить асинхронні api для стандартних функцій
+//! Тому якщо у вас колись буде вибір між використанням `std::XXX` та `tokio::XXX`
+//! у більшості випадків варто надати перевагу останньому.
+//! ## Axum
+//! `axum` це бекенд фреймворк.
+//! Для озайомлення з його можливосятями відівідайте [https://github.com/tokio-rs/axum/tree/main/examples](https://github.com/tokio-rs/axum/tree/main/examples)
+//! Оберіть бажану фічу та ознайомтеся з імлементацією
+//! ## Cookbook
+//! Іструкції для додавння функціоналу до цієї програми
+//! ### Ви хочете додати новий шаблон та ендпоінт для нього
+//! Для створення шаблону вам варто:
+//! - створити `html` файл у `crates/server/templates/`
+//! - створити `struct` (об'єкт) у `crates/server/src/templates.rs`
+//! - ендпоінт, що буде відповідати на запит
+//! - якщо, ви використовуєте html форму для отримання вводу користувача - поперньо створіть `struct` що відображає зміст цієї форми
+//! ___
+//! Ми хочемо взяти у користувача слово (`word`) та кількість (`number`), ми повернемо користувачу `ul>li{${rev_word}}*${number}`, де `rev_word` це слово задом-наперед.
+//! Спершу варто всяти у користувача ввід. Додаймо `html` форму до нашого `index.html`:
+//! ```html
+//! <form hx-post="/word_number">
+//!   <input name="word" type="text">
+//!   <input name="number" type="number" min="0" step="1">
+//!   <button type="submit">Submit</button>
+//! </form>
+//! ```
+//! Тепер варто додати `struct` до `server.rs`, що відображає форму
+//!
+//! ```
+//! #[derive(Debug, Deserialize)]
+//! pub struct WordNumber {
+//!     pub word: String,
+//!     pub number: u32,
+//! }
+//! ```
+//! При визначенні форми ми вказали намір роботи `POST` запити на `/word_number`
+//! Додаймо цей ендпоінт до нашого роутера
+//! ```
+//! let app = Router::new()
+//!     .route("/", get(index))
+//!     .route("/word_number", post(word_number)) // <== наш новий ендпоінт
+//!     .with_state(server_state);
+//! ```
+//! Перед тим як створити функцію `word_number`, створімо `html` шаблон, у `templates`
+//! нехай це буде `templates/word_number.html`
+//! ```jinja
+//! <ul>
+//!   {% for _ in range(end=number) %}
+//!     <li>{{ rev_word }}</li>
+//!   {% endfor %}
+//! </ul>
+//! ```
+//! Додамо обробник шаблона до файлу `src/templates.rs`,
+//! це `struct` який відоражає ввід для нашого шаблона,
+//! ```
+//! #[derive(Template)]
+//! #[template(path = "word_number.html")]
+//! pub struct WordNumber {
+//!     pub rev_word: String,
+//!     pub number: u32,
+//! }
+//! ```
+//! `<'a>` поруч з `WordNumber` та `rev_word` означає, що `rev_word` мусить померти (бути стертим з пам'яті) по смерті `WordNumber`
+//! Додамо обробника ендпоінта, раніше ми вказали, що цим буде займатися `word_number`
+//! ```
+//! async fn word_number(Form(WordNumber { word, number })) -> Result<Html<String>,StatusCode> {
+//!     let rev_word: String = word.chars().rev().collect();
+//!     let word_number = templates::WordNumber { rev_word, number };
+//!     let html = word_number
+//!         .render()
+//!         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+//!
+//!     Ok(Html(html))
+//! }
+//! ```
+mod api;
+mod server;
+mod templates;
+
+use crate::server::{
+    ActivePolymarketSearch, HedgeRequest, HedgeScenarioRequest, LiveUpdate, LoadAccount,
+    ServerState,
+};
+use application::hedge::Side;
+use application::prices::DEFAULT_COINS;
+use application::terms::Settlement;
+use application::{AppError, AssetKey, AssetsRow, TokenMetadataResolver};
+use application::{ClobClient, ClobFeed, FillTarget, Venue, sizing};
+use application::{Cluster, SolanaConfig, SolanaRpc};
+use application::{CoinGeckoClient, CorrelationTracker, HistorySource, PriceHistory};
+use application::{KalshiClient, MarketCatalog, PolymarketClient};
+use application::{LamportBalance, PolymarketSolana260, PriceOracle, PriceRegistry};
+
+use askama::Template;
+use axum::{
+    Form, Json,
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    response::{
+        Html, IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+    routing::get,
+};
+use std::{
+    convert::Infallible,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    fs,
+    sync::{RwLock, broadcast, watch},
+};
+use tokio_stream::{Stream, StreamExt};
+use tower_http::services::ServeDir;
+use utoipa::OpenApi;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+/// ## main
+/// Це роутер, тут визначаються енд поінти які може обробити сервер.
+/// ### route
+/// Для додавання нового ендпоінта
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let solana = configured_solana()?;
+    println!("Reading Solana {}", solana.cluster);
+    let server_state = server_state_updater(solana).await;
+
+    let (router, openapi) = documented_routes().split_for_parts();
+
+    let app = router
+        .route("/", get(index))
+        .route("/favicon.ico", get(favicon))
+        .route("/calculator", get(calculator_body))
+        .route("/api/openapi.json", get(|| async move { Json(openapi) }))
+        .nest_service("/css", ServeDir::new("crates/server/static/css"))
+        .nest_service("/js", ServeDir::new("crates/server/static/js"))
+        .nest_service("/images", ServeDir::new("crates/server/static/images"))
+        .nest_service("/effects", ServeDir::new("crates/server/static/effects"))
+        .nest_service("/static", ServeDir::new("crates/server/static"))
+        .with_state(server_state);
+
+    let port = std::env::var("PORT").unwrap_or_else(|_| "8081".to_string());
+    let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{port}")).await?;
+    println!("Running on http://0.0.0.0:{port}");
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+/// Ендпоінти, що потрапляють у OpenAPI специфікацію.
+/// Статичні сторінки та файли до неї не входять.
+fn documented_routes() -> OpenApiRouter<ServerState> {
+    OpenApiRouter::with_openapi(api::ApiDoc::openapi())
+        .routes(routes!(account))
+        .routes(routes!(positions))
+        .routes(routes!(calc))
+        .routes(routes!(tracked_markets))
+        .routes(routes!(hedges))
+        .routes(routes!(hedge_scenario))
+        .routes(routes!(live_events))
+        .nest("/api/v1", api::router())
+}
+
+async fn calculator_body() -> Result<Html<String>, StatusCode> {
+    match tokio::fs::read_to_string("crates/server/templates/calculator.html").await {
+        Ok(html) => Ok(Html(html)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/calculator",
+    tag = "htmx",
+    request_body(content = ActivePolymarketSearch, content_type = "application/x-www-form-urlencoded"),
+    responses((status = 200, content_type = "text/html", body = String))
+)]
+async fn calc(
+    State(ServerState {
+        polymarket_solana260,
+        ..
+    }): State<ServerState>,
+    Form(ActivePolymarketSearch { money }): Form<ActivePolymarketSearch>,
+) -> Result<Html<String>, StatusCode> {
+    let (polymarket_solana260_multiplyer, no_token_id) = {
+        let guard = polymarket_solana260.read().await;
+        (guard.answer_no_multiplier, guard.no_token_id.clone())
+    };
+
+    // Walk the real order book when we can, the last price ignores liquidity.
+    let trade = match no_token_id {
+        Some(token_id) => ClobClient::new()
+            .estimate_trade(&token_id, FillTarget::Usd(money), sizing::POLYMARKET_FEE)
+            .await
+            .ok(),
+        None => None,
+    };
+
+    let html = match trade {
+        Some(trade) if trade.shares > 0.0 => {
+            let mut html = format!(
+                "{:.2}$ <small>avg {:.3}$, slippage {:+.3}$</small>",
+                trade.shares, trade.price_per_share, trade.slippage
+            );
+            if trade.unfilled_usd > 0.0 {
+                html.push_str(&format!(
+                    " <small>(only {:.2}$ fillable)</small>",
+                    money - trade.unfilled_usd
+                ));
+            }
+            html
+        }
+        _ => {
+            let bet_return = money / polymarket_solana260_multiplyer;
+            format!("{bet_return:.2}$")
+        }
+    };
+    Ok(Html(html))
+}
+
+#[utoipa::path(
+    post,
+    path = "/account",
+    tag = "htmx",
+    request_body(content = LoadAccount, content_type = "application/x-www-form-urlencoded"),
+    responses((status = 200, content_type = "text/html", body = String))
+)]
+async fn account(
+    State(ServerState {
+        solana,
+        price_registry,
+        ..
+    }): State<ServerState>,
+    Form(LoadAccount { account_id }): Form<LoadAccount>,
+) -> Result<Html<String>, StatusCode> {
+    let rate = price_registry.read().await.get_sol_to_usd();
+    let lamport_balance = LamportBalance::get(&solana, account_id)
+        .await
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let sol = &format!("{:.2}", lamport_balance.to_sol());
+    let usd = &format!("{:.2}", lamport_balance.to_usd(rate));
+    let rate = &format!("{:.2}", rate);
+
+    let exchange_prices = templates::ExchangeRate { sol, usd, rate };
+    let html = exchange_prices
+        .render()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Html(html))
+}
+
+#[utoipa::path(
+    post,
+    path = "/positions",
+    tag = "htmx",
+    request_body(content = LoadAccount, content_type = "application/x-www-form-urlencoded"),
+    responses((status = 200, content_type = "text/html", body = String))
+)]
+async fn positions(
+    State(state): State<ServerState>,
+    Form(LoadAccount { account_id }): Form<LoadAccount>,
+) -> Result<Html<String>, StatusCode> {
+    let ServerState {
+        price_registry,
+        price_oracle,
+        ..
+    } = &state;
+    let wallet_service = api::wallet_service(&state);
+
+    let wallet_assets = wallet_service
+        .get_wallet_assets(&account_id)
+        .await
+        .map_err(StatusCode::from)?;
+    api::learn_prices(price_registry, price_oracle, &wallet_assets).await;
+    let exchange_rates = price_registry.read().await;
+
+    let assets_rows: Vec<AssetsRow> = wallet_assets
+        .into_iter()
+        .map(|asset| {
+            let usd_value = if let Some(price) = asset.usd_price(&exchange_rates) {
+                let balance: f64 = asset.balance.parse().unwrap_or(0.0); // Use asset.balance instead of asset.amount
+                format!("${:.2}", balance * price)
+            } else {
+                "N/A".to_string()
+            };
+
+            AssetsRow {
+                value: usd_value,
+                ..asset
+            }
+        })
+        .collect();
+
+    let html = templates::AccountAssets { assets_rows }
+        .render()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Html(html))
+}
+
+#[utoipa::path(
+    post,
+    path = "/hedges",
+    tag = "htmx",
+    request_body(content = HedgeRequest, content_type = "application/x-www-form-urlencoded"),
+    responses((status = 200, content_type = "text/html", body = String))
+)]
+async fn hedges(
+    State(server_state): State<ServerState>,
+    Form(HedgeRequest {
+        account_id,
+        coverage,
+    }): Form<HedgeRequest>,
+) -> Result<Html<String>, StatusCode> {
+    let suggestions = api::hedge_suggestions(&server_state, &account_id, coverage)
+        .await
+        .map_err(StatusCode::from)?;
+
+    let rows = suggestions
+        .into_iter()
+        .map(|suggestion| templates::HedgeSuggestionRow {
+            url: suggestion.market.url(),
+            market: suggestion.market.question,
+            side: suggestion.side.outcome().to_string(),
+            size: format!("{:.1}%", suggestion.cost_pct),
+            to_win: format!("${:.2}", suggestion.max_payout),
+            to_pay: format!("${:.2}", suggestion.stake),
+            protects: format!(
+                "{} below ${:.0}",
+                suggestion.holding.asset, suggestion.protected_range.high
+            ),
+            edge: suggestion.edge.map(|edge| {
+                format!(
+                    "model {:.0}% vs price {:.0}%",
+                    edge.model * 100.0,
+                    edge.implied * 100.0
+                )
+            }),
+            forecast: suggestion
+                .forecast_band
+                .map(|band| format!("${:.0} - ${:.0}", band.low, band.high)),
+            venue: format!("{:?}", suggestion.market.venue),
+            id: suggestion.market.id,
+        })
+        .collect();
+
+    let html = templates::HedgeSuggestions { rows }
+        .render()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Html(html))
+}
+
+/// Returns the scenario table for htmx, or the scenario itself as JSON
+/// when the request `Accept`s `application/json`.
+#[utoipa::path(
+    post,
+    path = "/hedge/scenario",
+    tag = "htmx",
+    request_body(content = HedgeScenarioRequest, content_type = "application/x-www-form-urlencoded"),
+    responses((status = 200, content(
+        (String = "text/html"),
+        (application::HedgeScenario = "application/json"),
+    )))
+)]
+async fn hedge_scenario(
+    State(server_state): State<ServerState>,
+    headers: HeaderMap,
+    Form(request): Form<HedgeScenarioRequest>,
+) -> Result<Response, StatusCode> {
+    let scenario = api::hedge_scenario(&server_state, request)
+        .await
+        .map_err(|err| err.status)?;
+    let spot_price = scenario.holding.spot_price;
+
+    let wants_json = headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("application/json"));
+    if wants_json {
+        return Ok(Json(scenario).into_response());
+    }
+
+    let usd = |value: f64| format!("{}${:.2}", if value < 0.0 { "-" } else { "" }, value.abs());
+    let rows = scenario
+        .points
+        .iter()
+        .map(|point| templates::ScenarioRow {
+            price: format!("${:.2}", point.terminal_price),
+            touched: match point.touched {
+                Some(true) => "yes".to_string(),
+                Some(false) => "no".to_string(),
+                None => String::new(),
+            },
+            spot_pnl: usd(point.spot_pnl),
+            hedge_pnl: usd(point.hedge_pnl),
+            net_pnl: usd(point.net_pnl),
+            is_spot: (point.terminal_price - spot_price).abs() < spot_price * 1e-9,
+            is_strike: point.terminal_price == scenario.terms.strike,
+        })
+        .collect();
+    let breakevens = scenario
+        .breakevens
+        .iter()
+        .map(|price| format!("${:.2}", price))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let html = templates::HedgeScenario {
+        market: scenario.market.question.clone(),
+        side: scenario.side.outcome().to_string(),
+        shares: format!("{:.2}", scenario.size.shares),
+        stake: usd(scenario.size.stake),
+        max_payout: usd(scenario.size.max_payout),
+        breakevens,
+        barrier: scenario.terms.settlement == Settlement::Barrier,
+        rows,
+    }
+    .render()
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Html(html).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/markets",
+    tag = "htmx",
+    responses((status = 200, content_type = "text/html", body = String))
+)]
+async fn tracked_markets(
+    State(server_state): State<ServerState>,
+) -> Result<Html<String>, StatusCode> {
+    let volatility = api::volatility_model(&server_state).await;
+    let rows = server_state
+        .market_catalog
+        .read()
+        .await
+        .entries()
+        .map(|entry| templates::TrackedMarketRow {
+            venue: format!("{:?}", entry.market.venue),
+            question: entry.market.question.clone(),
+            url: entry.market.url(),
+            asset: entry.terms.asset.to_string(),
+            direction: format!("{:?} ({:?})", entry.terms.direction, entry.terms.settlement),
+            strike: format!("${:.0}", entry.terms.strike),
+            expiry: entry
+                .terms
+                .expiry
+                .map(|expiry| expiry.format("%Y-%m-%d").to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            yes_price: entry
+                .market
+                .yes_price()
+                .map(|price| format!("{:.3}", price))
+                .unwrap_or_else(|| "N/A".to_string()),
+            edge: volatility
+                .edge(&entry.market, &entry.terms, Side::Yes)
+                .map(|edge| format!("{:+.1} pp", edge.edge * 100.0))
+                .unwrap_or_else(|| "-".to_string()),
+        })
+        .collect();
+
+    let html = templates::TrackedMarkets { rows }
+        .render()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Html(html))
+}
+
+/// Live prices and odds for the htmx SSE extension:
+/// `sse-connect="/events"` with `sse-swap="prices"` or `sse-swap="odds"`.
+#[utoipa::path(
+    get,
+    path = "/events",
+    tag = "htmx",
+    responses((status = 200, content_type = "text/event-stream", body = String))
+)]
+async fn live_events(
+    State(server_state): State<ServerState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = server_state.live_updates().await.map(|update| {
+        let html = match &update {
+            LiveUpdate::Prices(quotes) => quotes
+                .iter()
+                .filter_map(|quote| match &quote.key {
+                    AssetKey::Symbol { symbol } => Some(format!("{symbol} {:.2}$", quote.usd)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join(" · "),
+            LiveUpdate::Odds(odds) => {
+                format!("SOL 260 \"No\" ×{:.2}", odds.answer_no_multiplier)
+            }
+        };
+        Ok(Event::default().event(update.event_name()).data(html))
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+async fn index() -> Result<Html<String>, StatusCode> {
+    let index = fs::read_to_string("crates/server/templates/index.html")
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Html(index))
+}
+
+async fn favicon() -> axum::response::Redirect {
+    axum::response::Redirect::permanent("/static/svg/icon.svg")
+}
+
+/// `PRICE_COINS="SOL=solana,BTC=bitcoin,JUP=jupiter-exchange-solana"` overrides
+/// the CoinGecko ids the updater fetches.
+fn configured_coins() -> Vec<(String, String)> {
+    match std::env::var("PRICE_COINS") {
+        Ok(coins) => coins
+            .split(',')
+            .filter_map(|pair| pair.trim().split_once('='))
+            .map(|(symbol, id)| (symbol.trim().to_string(), id.trim().to_string()))
+            .collect(),
+        Err(_) => DEFAULT_COINS
+            .iter()
+            .map(|(symbol, id)| (symbol.to_string(), id.to_string()))
+            .collect(),
+    }
+}
+
+/// `SOLANA_CLUSTER` is `mainnet-beta` (the default), `devnet`, `testnet`,
+/// `localnet` for `solana-test-validator`, or an RPC URL.
+/// `SOLANA_RPC_AUTH_HEADER="x-api-key: ..."` is sent with every RPC request.
+fn configured_solana() -> Result<SolanaConfig, AppError> {
+    let cluster = match std::env::var("SOLANA_CLUSTER") {
+        Ok(cluster) => cluster.parse()?,
+        Err(_) => Cluster::default(),
+    };
+    let config = SolanaConfig::new(cluster);
+    match std::env::var("SOLANA_RPC_AUTH_HEADER") {
+        Ok(header) => config.with_auth_header(&header),
+        Err(_) => Ok(config),
+    }
+}
+
+/// Days of price history fetched from CoinGecko on startup.
+const BACKFILL_DAYS: u32 = 30;
+
+/// `PRICE_HISTORY_FILE` overrides where price snapshots are kept,
+/// `data/price-history.jsonl` by default. History stays in memory only when
+/// the file cannot be opened.
+async fn open_price_history() -> PriceHistory {
+    let path = std::env::var("PRICE_HISTORY_FILE")
+        .unwrap_or_else(|_| "data/price-history.jsonl".to_string());
+    PriceHistory::open(&path).await.unwrap_or_else(|err| {
+        eprintln!("price history is kept in memory only: {err}");
+        PriceHistory::new()
+    })
+}
+
+async fn server_state_updater(solana: SolanaConfig) -> ServerState {
+    let (live_updates, _) = broadcast::channel(16);
+    let coins = configured_coins();
+    let solana = Arc::new(SolanaRpc::new(&solana));
+    let price_registry = Arc::new(RwLock::new(PriceRegistry::with_coins(coins.clone())));
+    let price_oracle = Arc::new(PriceOracle::with_coins(coins.clone(), Arc::clone(&solana)));
+    let price_history = Arc::new(RwLock::new(open_price_history().await));
+    let history_source = Arc::new(HistorySource::with_coins(
+        CoinGeckoClient::new(),
+        coins.clone(),
+    ));
+    let token_metadata = Arc::new(TokenMetadataResolver::new(Arc::clone(&solana)));
+    let polymarket_solana260 = Arc::new(RwLock::new(PolymarketSolana260::new()));
+    let market_catalog = Arc::new(RwLock::new(MarketCatalog::new()));
+    let market_correlations = Arc::new(RwLock::new(CorrelationTracker::new()));
+
+    let price_registry_clone = Arc::clone(&price_registry);
+    let polymarket_solana260_clone = Arc::clone(&polymarket_solana260);
+    let market_catalog_clone = Arc::clone(&market_catalog);
+    let prices_sender = live_updates.clone();
+    let odds_sender = live_updates.clone();
+    let (feed_tokens, feed_tokens_rx) = watch::channel(Vec::new());
+    let clob_feed = ClobFeed::new();
+    let price_oracle_clone = Arc::clone(&price_oracle);
+    let price_history_clone = Arc::clone(&price_history);
+    let backfill_history = Arc::clone(&price_history);
+    let backfill_source = Arc::clone(&history_source);
+
+    tokio::spawn(async move {
+        for (symbol, _) in coins {
+            let key = AssetKey::symbol(&symbol);
+            if let Err(err) =
+                api::load_history(&backfill_history, &backfill_source, &key, BACKFILL_DAYS).await
+            {
+                eprintln!("price history backfill for {symbol} failed: {err}");
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(30));
+        loop {
+            interval.tick().await;
+            let request = {
+                let mut guard = price_registry_clone.write().await;
+                guard.expire();
+                guard.refresh_request()
+            };
+            // The lock is not held while CoinGecko answers.
+            match request.fetch(&price_oracle_clone).await {
+                Ok(update) => {
+                    if !update.failed.is_empty() {
+                        eprintln!("price sources failed: {}", update.failed.join(", "));
+                    }
+                    let quotes = {
+                        let mut guard = price_registry_clone.write().await;
+                        guard.record(update);
+                        guard.quotes()
+                    };
+                    let appended = price_history_clone.write().await.record(&quotes);
+                    if let Err(err) = appended.write().await {
+                        eprintln!("price snapshot was not saved: {err}");
+                    }
+                    // No subscribers is not an error.
+                    let _ = prices_sender.send(LiveUpdate::Prices(quotes));
+                }
+                Err(err) => eprintln!("price refresh failed: {err}"),
+            }
+        }
+    });
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(300));
+        loop {
+            interval.tick().await;
+            match PolymarketSolana260::fetch().await {
+                Ok(new_polymarket_solana260) => {
+                    *polymarket_solana260_clone.write().await = new_polymarket_solana260.clone();
+                    let _ = odds_sender.send(LiveUpdate::Odds(new_polymarket_solana260));
+                }
+                _ => continue,
+            }
+        }
+    });
+
+    // Between the polls above the odds follow the CLOB market channel.
+    let feed = clob_feed.clone();
+    tokio::spawn(async move { feed.run(feed_tokens_rx).await });
+    tokio::spawn({
+        let polymarket_solana260 = Arc::clone(&polymarket_solana260);
+        let odds_sender = live_updates.clone();
+        async move {
+            let mut quotes = clob_feed.subscribe();
+            loop {
+                let update = match quotes.recv().await {
+                    Ok(update) => update,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let mut guard = polymarket_solana260.write().await;
+                if guard.no_token_id.as_deref() == Some(update.token_id.as_str())
+                    && guard.apply_quote(&update.quote)
+                {
+                    let _ = odds_sender.send(LiveUpdate::Odds(guard.clone()));
+                }
+            }
+        }
+    });
+
+    // Keeps the feed subscribed to the catalog and the SOL 260 market.
+    tokio::spawn({
+        let polymarket_solana260 = Arc::clone(&polymarket_solana260);
+        let market_catalog = Arc::clone(&market_catalog);
+        async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                let mut tokens: Vec<String> = market_catalog
+                    .read()
+                    .await
+                    .entries()
+                    .filter(|entry| entry.market.venue == Venue::Polymarket)
+                    .flat_map(|entry| entry.market.token_ids.iter().cloned())
+                    .collect();
+                tokens.extend(polymarket_solana260.read().await.no_token_id.clone());
+                tokens.sort();
+                tokens.dedup();
+                feed_tokens.send_if_modified(|current| {
+                    let changed = *current != tokens;
+                    *current = tokens;
+                    changed
+                });
+            }
+        }
+    });
+
+    let market_correlations_clone = Arc::clone(&market_correlations);
+    tokio::spawn(async move {
+        let polymarket = PolymarketClient::new();
+        let kalshi = KalshiClient::new();
+        let mut interval = tokio::time::interval(Duration::from_secs(300));
+        loop {
+            interval.tick().await;
+            match MarketCatalog::discover(&polymarket, &kalshi).await {
+                Ok(new_market_catalog) => {
+                    market_correlations_clone.write().await.record(
+                        new_market_catalog
+                            .entries()
+                            .map(|entry| &entry.market)
+                            .chain(new_market_catalog.unmatched()),
+                        SystemTime::now(),
+                    );
+                    *market_catalog_clone.write().await = new_market_catalog;
+                }
+                _ => continue,
+            }
+        }
+    });
+
+    ServerState {
+        solana,
+        price_registry,
+        price_oracle,
+        price_history,
+        history_source,
+        token_metadata,
+        polymarket_solana260,
+        market_catalog,
+        market_correlations,
+        live_updates,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// The committed `openapi.json` is the contract the frontend and partners
+    /// build against. Run with `UPDATE_OPENAPI=1` to accept a change.
+    #[test]
+    fn openapi_spec_matches_handlers() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("openapi.json");
+        let generated = documented_routes().into_openapi().to_pretty_json().unwrap();
+
+        if std::env::var_os("UPDATE_OPENAPI").is_some() {
+            std::fs::write(&path, generated + "\n").unwrap();
+            return;
+        }
+
+        let committed = std::fs::read_to_string(&path).expect("openapi.json is missing");
+        let committed: serde_json::Value = serde_json::from_str(&committed).unwrap();
+        let generated: serde_json::Value = serde_json::from_str(&generated).unwrap();
+        assert!(
+            committed == generated,
+            "openapi.json is out of date, rerun the tests with UPDATE_OPENAPI=1"
+        );
+    }
+
+    #[test]
+    fn every_api_route_is_documented() {
+        let spec = documented_routes().into_openapi();
+        for path in [
+            "/account",
+            "/positions",
+            "/calculator",
+            "/markets",
+            "/hedges",
+            "/hedge/scenario",
+            "/events",
+            "/api/v1/accounts/{address}/balance",
+            "/api/v1/accounts/{address}/positions",
+            "/api/v1/accounts/{address}/hedges",
+            "/api/v1/prices",
+            "/api/v1/prices/{asset}/ohlc",
+            "/api/v1/prices/{asset}/forecast",
+            "/api/v1/markets",
+            "/api/v1/correlations",
+            "/api/v1/hedge/scenario",
+            "/api/v1/events",
+        ] {
+            assert!(
+                spec.paths.paths.contains_key(path),
+                "{path} is not documented"
+            );
+        }
+    }
+}
@@ -1,20 +1,88 @@
-use application::{ExchangePrices, PolymarketSolana260};
-use serde::Deserialize;
-use std::sync::Arc;
-use tokio::sync::RwLock;
-
-#[derive(Clone)]
-pub struct ServerState {
-    pub exchange_prices: Arc<RwLock<ExchangePrices>>,
-    pub polymarket_solana260: Arc<RwLock<PolymarketSolana260>>,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct ActivePolymarketSearch {
-    pub money: f64,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct LoadAccount {
-    pub account_id: String,
-}
+use application::SolanaRpc;
+use application::{CorrelationTracker, HistorySource, PriceHistory, TokenMetadataResolver};
+use application::{
+    MarketCatalog, PolymarketSolana260, PriceOracle, PriceQuote, PriceRegistry, Venue,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::{RwLock, broadcast};
+use tokio_stream::{Stream, StreamExt, wrappers::BroadcastStream};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Clone)]
+pub struct ServerState {
+    /// RPC client of the configured cluster, every chain read goes through it.
+    pub solana: Arc<SolanaRpc>,
+    pub price_registry: Arc<RwLock<PriceRegistry>>,
+    /// Where `price_registry` gets its prices from.
+    pub price_oracle: Arc<PriceOracle>,
+    /// Snapshots of `price_registry`, what the charts are drawn from.
+    pub price_history: Arc<RwLock<PriceHistory>>,
+    pub history_source: Arc<HistorySource>,
+    /// Symbols, names and logos of the mints wallets hold, cached.
+    pub token_metadata: Arc<TokenMetadataResolver>,
+    pub polymarket_solana260: Arc<RwLock<PolymarketSolana260>>,
+    pub market_catalog: Arc<RwLock<MarketCatalog>>,
+    /// YES prices of the catalog and unmatched markets, sampled with it.
+    pub market_correlations: Arc<RwLock<CorrelationTracker>>,
+    /// Every value the background tasks write is also sent here.
+    pub live_updates: broadcast::Sender<LiveUpdate>,
+}
+
+#[derive(Debug, Clone)]
+pub enum LiveUpdate {
+    Prices(Vec<PriceQuote>),
+    Odds(PolymarketSolana260),
+}
+
+impl LiveUpdate {
+    /// SSE event name, what `sse-swap` listens for.
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            LiveUpdate::Prices(_) => "prices",
+            LiveUpdate::Odds(_) => "odds",
+        }
+    }
+}
+
+impl ServerState {
+    /// Current prices and odds first, then every update as it is written.
+    /// A subscriber that falls behind skips the updates it missed.
+    pub async fn live_updates(&self) -> impl Stream<Item = LiveUpdate> + use<> {
+        let updates = BroadcastStream::new(self.live_updates.subscribe());
+        let current = [
+            LiveUpdate::Prices(self.price_registry.read().await.quotes()),
+            LiveUpdate::Odds(self.polymarket_solana260.read().await.clone()),
+        ];
+
+        tokio_stream::iter(current).chain(updates.filter_map(Result::ok))
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ActivePolymarketSearch {
+    pub money: f64,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LoadAccount {
+    pub account_id: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct HedgeRequest {
+    pub account_id: String,
+    /// Percentage of the loss down to the strike to protect.
+    pub coverage: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HedgeScenarioRequest {
+    pub asset: String,
+    pub quantity: f64,
+    pub venue: Venue,
+    pub market_id: String,
+    /// Percentage of the loss down to the strike to protect, 100 by default.
+    pub coverage: Option<f64>,
+}
@@ -1,16 +1,80 @@
-use askama::Template;
-use serde::Serialize;
-
-#[derive(Template)]
-#[template(path = "exchange-rate.html")]
-pub struct ExchangeRate<'a> {
-    pub sol: &'a str,
-    pub usd: &'a str,
-    pub rate: &'a str,
-}
-
-#[derive(Template)]
-#[template(path = "user-assets.html")]
-pub struct AccountAssets {
-    pub assets_rows: Vec<application::AssetsRow>,
-}
+use askama::Template;
+
+#[derive(Template)]
+#[template(path = "exchange-rate.html")]
+pub struct ExchangeRate<'a> {
+    pub sol: &'a str,
+    pub usd: &'a str,
+    pub rate: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "user-assets.html")]
+pub struct AccountAssets {
+    pub assets_rows: Vec<application::AssetsRow>,
+}
+
+pub struct TrackedMarketRow {
+    pub venue: String,
+    pub question: String,
+    pub url: String,
+    pub asset: String,
+    pub direction: String,
+    pub strike: String,
+    pub expiry: String,
+    pub yes_price: String,
+    /// Model minus implied YES probability, in percentage points.
+    pub edge: String,
+}
+
+#[derive(Template)]
+#[template(path = "tracked-markets.html")]
+pub struct TrackedMarkets {
+    pub rows: Vec<TrackedMarketRow>,
+}
+
+pub struct HedgeSuggestionRow {
+    pub market: String,
+    pub url: String,
+    pub side: String,
+    pub size: String,
+    pub to_win: String,
+    pub to_pay: String,
+    pub protects: String,
+    /// 3-day forecast band of the asset, when there is enough history.
+    pub forecast: Option<String>,
+    pub edge: Option<String>,
+    pub venue: String,
+    pub id: String,
+}
+
+#[derive(Template)]
+#[template(path = "hedge-suggestions.html")]
+pub struct HedgeSuggestions {
+    pub rows: Vec<HedgeSuggestionRow>,
+}
+
+pub struct ScenarioRow {
+    pub price: String,
+    /// `yes` or `no` for barrier markets, empty otherwise.
+    pub touched: String,
+    pub spot_pnl: String,
+    pub hedge_pnl: String,
+    pub net_pnl: String,
+    pub is_spot: bool,
+    pub is_strike: bool,
+}
+
+#[derive(Template)]
+#[template(path = "hedge-scenario.html")]
+pub struct HedgeScenario {
+    pub market: String,
+    pub side: String,
+    pub shares: String,
+    pub stake: String,
+    pub max_payout: String,
+    pub breakevens: String,
+    /// Rows say whether the strike was touched.
+    pub barrier: bool,
+    pub rows: Vec<ScenarioRow>,
+}